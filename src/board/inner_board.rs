@@ -37,6 +37,26 @@ impl<Cell: Default> Board<Cell> {
     pub fn cell_mut<'a>(&'a mut self, p: &BoardPosition) -> Option<&'a mut Cell> {
         self.cells.get_mut(p)
     }
+
+    pub fn contains(&self, p: &BoardPosition) -> bool {
+        (0..self.size.x() as i64).contains(&p.x) && (0..self.size.y() as i64).contains(&p.y)
+    }
+
+    // Inside the size but without a cell, a hole that iterators stop at.
+    pub fn is_blocked(&self, p: &BoardPosition) -> bool {
+        self.contains(p) && !self.cells.contains_key(p)
+    }
+
+    pub fn block(&mut self, p: &BoardPosition) -> bool {
+        self.cells.remove(p).is_some()
+    }
+
+    // Every cell back to default, the holes stay.
+    pub fn clear(&mut self) {
+        self.cells
+            .values_mut()
+            .for_each(|cell| *cell = Cell::default());
+    }
 }
 
 impl<Cell: Clone + Default> Clone for Board<Cell> {
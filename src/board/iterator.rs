@@ -60,13 +60,17 @@ where
                 self.state = Started;
                 self.position.apply_direction(&self.direction, self.step);
                 let position = self.position;
-                let result = self.current_cell_mut();
-                result.map(|c| unsafe {
+                let result = self.current_cell_mut().map(|c| unsafe {
                     (
                         position,
                         &mut *(c as *mut Cell), // this is for bypassing the lifetime
                     )
-                })
+                });
+                // a missing cell is a wall even right next to the start
+                if result.is_none() {
+                    self.state = End;
+                }
+                result
             }
             Started => {
                 self.position.apply_direction(&self.direction, self.step);
@@ -139,13 +143,17 @@ where
                 self.state = Started;
                 self.position.apply_direction(&self.direction, self.step);
                 let position = self.position;
-                let result = self.current_cell();
-                result.map(|c| unsafe {
+                let result = self.current_cell().map(|c| unsafe {
                     (
                         position,
                         &*(c as *const Cell), // this is for bypassing the lifetime
                     )
-                })
+                });
+                // a missing cell is a wall even right next to the start
+                if result.is_none() {
+                    self.state = End;
+                }
+                result
             }
             Started => {
                 self.position.apply_direction(&self.direction, self.step);
@@ -176,6 +184,23 @@ mod test {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn stops_at_missing_cells() {
+        let size: Size = (4, 1).try_into().unwrap();
+        let mut board = Board::<()>::new(size);
+        board.block(&(1, 0).into());
+        assert!(board.is_blocked(&(1, 0).into()));
+        assert!(!board.is_blocked(&(4, 0).into()));
+
+        let mut iter = Iter::new(&board, BoardPosition { x: 0, y: 0 }, Direction::Right, 1);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+        let mut iter = Iter::new(&board, BoardPosition { x: 3, y: 0 }, Direction::Left, 1);
+        assert_eq!(iter.next().map(|(pos, _)| pos), Some((2, 0).into()));
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
     #[test]
     fn mutate() {
         type Cell = Option<()>;
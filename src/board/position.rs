@@ -1,11 +1,12 @@
 use super::Direction;
+use serde::{Deserialize, Serialize};
 use std::ops::AddAssign;
 
 pub type PositionUnit = i64;
 
 pub type Magnitude = u8;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct BoardPosition {
     pub x: PositionUnit,
     pub y: PositionUnit,
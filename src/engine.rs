@@ -0,0 +1,316 @@
+// Line based engine protocol over stdin/stdout in the spirit of GTP, for external GUIs and match runners.
+// A request is "[id] command [arguments]", a response is "=[id] result" or "?[id] error"
+// followed by an empty line.
+use std::io::{BufRead, Write};
+
+use crate::game_state::{
+    ai,
+    data::{BoardSize, GameData, Player, Turn},
+    notation,
+    rules::{MoveError, RuleSet},
+};
+use crate::tui;
+
+const ENGINE_NAME: &str = "reversi";
+const PROTOCOL_VERSION: &str = "2";
+const DEFAULT_BOARD_SIZE: u16 = 8;
+const PASS: &str = "pass";
+
+const COMMANDS: [&str; 14] = [
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "play",
+    "genmove",
+    "undo",
+    "showboard",
+    "final_score",
+    "transcript",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EngineError {
+    UnknownCommand(String),
+    MissingArgument,
+    InvalidColor(String),
+    InvalidVertex(String),
+    InvalidSize(String),
+    WrongTurn(Player),
+    IllegalMove(MoveError),
+    CannotUndo,
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::UnknownCommand(command) => write!(f, "unknown command {}", command),
+            EngineError::MissingArgument => write!(f, "missing argument"),
+            EngineError::InvalidColor(color) => write!(f, "invalid color {}", color),
+            EngineError::InvalidVertex(vertex) => write!(f, "invalid vertex {}", vertex),
+            EngineError::InvalidSize(size) => write!(f, "unacceptable size {}", size),
+            EngineError::WrongTurn(player) => write!(f, "it is {:?}'s turn", player),
+            EngineError::IllegalMove(e) => write!(f, "illegal move {:?}", e),
+            EngineError::CannotUndo => write!(f, "cannot undo"),
+        }
+    }
+}
+
+impl From<MoveError> for EngineError {
+    fn from(value: MoveError) -> Self {
+        EngineError::IllegalMove(value)
+    }
+}
+
+fn parse_color(text: &str) -> Result<Player, EngineError> {
+    match text.to_ascii_lowercase().as_str() {
+        "b" | "black" => Ok(Player::Black),
+        "w" | "white" => Ok(Player::White),
+        _ => Err(EngineError::InvalidColor(text.to_string())),
+    }
+}
+
+fn new_game(size: u16) -> GameData {
+    let size = size.try_into().unwrap();
+    GameData::new(Turn::Black, size, size)
+}
+
+pub struct Engine {
+    game_data: GameData,
+    player_controller: ai::PlayerController, // plays genmove for either color
+}
+
+impl Engine {
+    pub fn new(player_controller: ai::PlayerController) -> Self {
+        Engine {
+            game_data: new_game(DEFAULT_BOARD_SIZE),
+            player_controller,
+        }
+    }
+
+    pub fn with_rule_set(mut self, rule_set: RuleSet) -> Self {
+        self.game_data = self.game_data.with_rule_set(rule_set);
+        self
+    }
+
+    // Answers requests until quit or the end of the input.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> std::io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let mut words = line.split_whitespace().peekable();
+            let id = words
+                .next_if(|word| word.chars().all(|c| c.is_ascii_digit()))
+                .unwrap_or_default();
+            let Some(command) = words.next() else {
+                continue;
+            };
+            let arguments: Vec<&str> = words.collect();
+
+            match self.execute(command, &arguments) {
+                Ok(result) if result.is_empty() => write!(output, "={}\n\n", id)?,
+                Ok(result) => write!(output, "={} {}\n\n", id, result)?,
+                Err(e) => write!(output, "?{} {}\n\n", id, e)?,
+            }
+            output.flush()?;
+            if command == "quit" {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn execute(&mut self, command: &str, arguments: &[&str]) -> Result<String, EngineError> {
+        let argument = |index: usize| arguments.get(index).ok_or(EngineError::MissingArgument);
+        match command {
+            "protocol_version" => Ok(PROTOCOL_VERSION.to_string()),
+            "name" => Ok(ENGINE_NAME.to_string()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            "known_command" => Ok(COMMANDS.contains(argument(0)?).to_string()),
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => Ok(String::new()),
+            "boardsize" => {
+                let text = argument(0)?;
+                let size = text
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|size| BoardSize::try_from(*size).is_ok())
+                    .ok_or_else(|| EngineError::InvalidSize(text.to_string()))?;
+                self.game_data = new_game(size).with_rule_set(self.game_data.rule_set());
+                Ok(String::new())
+            }
+            "clear_board" => {
+                self.game_data.reset();
+                Ok(String::new())
+            }
+            "play" => {
+                let player = parse_color(argument(0)?)?;
+                self.play(player, argument(1)?)?;
+                Ok(String::new())
+            }
+            "genmove" => {
+                let player = parse_color(argument(0)?)?;
+                self.genmove(player)
+            }
+            "undo" => {
+                if !self.game_data.undo() {
+                    return Err(EngineError::CannotUndo);
+                }
+                Ok(String::new())
+            }
+            "showboard" => Ok(format!(
+                "\n{}",
+                tui::render_board(&self.game_data).trim_end()
+            )),
+            "final_score" => {
+                // the rule set decides who wins, the margin is the disc difference either way
+                let score = self.game_data.score();
+                let margin = score.black.abs_diff(score.white);
+                Ok(match self.game_data.winner() {
+                    Player::Black => format!("B+{}", margin),
+                    Player::White => format!("W+{}", margin),
+                    Player::None => "0".to_string(),
+                })
+            }
+            "transcript" => Ok(self.game_data.transcript()),
+            command => Err(EngineError::UnknownCommand(command.to_string())),
+        }
+    }
+
+    // Passes for the player on turn when it can't move, so the other color may play.
+    fn start_turn(&mut self, player: Player) -> Result<(), EngineError> {
+        if self.game_data.current_player() != player && self.game_data.legal_moves().is_empty() {
+            self.game_data.pass()?;
+        }
+        if self.game_data.current_player() != player {
+            return Err(EngineError::WrongTurn(self.game_data.current_player()));
+        }
+        Ok(())
+    }
+
+    fn play(&mut self, player: Player, vertex: &str) -> Result<(), EngineError> {
+        self.start_turn(player)?;
+        if vertex.eq_ignore_ascii_case(PASS) {
+            self.game_data.pass()?;
+            return Ok(());
+        }
+        // moves are exchanged in the standard orientation like transcripts
+        let board = self.game_data.board();
+        let position = notation::parse(vertex)
+            .map(|position| notation::standard_orientation(&position, board.size))
+            .ok()
+            .filter(|position| board.contains(position))
+            .ok_or_else(|| EngineError::InvalidVertex(vertex.to_string()))?;
+        self.game_data.play(&position)?;
+        Ok(())
+    }
+
+    fn genmove(&mut self, player: Player) -> Result<String, EngineError> {
+        self.start_turn(player)?;
+        match self.player_controller.choose_move(&self.game_data) {
+            Some(position) => {
+                self.game_data.play(&position)?;
+                let size = self.game_data.board().size;
+                Ok(notation::to_string(&notation::standard_orientation(
+                    &position, size,
+                )))
+            }
+            None => {
+                self.game_data.pass()?;
+                Ok(PASS.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn engine() -> Engine {
+        let settings = ai::search::SearchSettings {
+            max_depth: 2,
+            ..Default::default()
+        };
+        Engine::new(ai::PlayerController::AlphaBeta(settings))
+    }
+
+    fn run(engine: &mut Engine, input: &str) -> String {
+        let mut output = vec![];
+        engine.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn scripted_session() {
+        let mut engine = engine();
+        let output = run(
+            &mut engine,
+            "1 name\n# comment\n\nplay black f5\nplay black d6\n2 play white z1\nplay white d6\nundo\nplay w f6\nfinal_score\nfoo\nquit\nname\n",
+        );
+        assert_eq!(
+            output,
+            "=1 reversi\n\n\
+             =\n\n\
+             ? it is White's turn\n\n\
+             ?2 invalid vertex z1\n\n\
+             =\n\n\
+             =\n\n\
+             =\n\n\
+             = 0\n\n\
+             ? unknown command foo\n\n\
+             =\n\n"
+        );
+        assert_eq!(engine.game_data.transcript(), "f5f6");
+    }
+
+    #[test]
+    fn genmove_plays_legal_moves() {
+        let mut engine = engine();
+        let response = engine.execute("genmove", &["black"]).unwrap();
+        assert!(["d3", "c4", "f5", "e6"].contains(&response.as_str()));
+        assert_eq!(
+            engine.execute("genmove", &["black"]),
+            Err(EngineError::WrongTurn(Player::White))
+        );
+        assert!(engine.execute("genmove", &["white"]).is_ok());
+        assert_eq!(engine.game_data.history().len(), 2);
+    }
+
+    #[test]
+    fn final_score_follows_rule_set() {
+        let mut engine = engine();
+        engine.execute("play", &["black", "f5"]).unwrap();
+        assert_eq!(engine.execute("final_score", &[]), Ok("B+3".to_string()));
+
+        let mut engine = engine.with_rule_set(RuleSet::Anti);
+        assert_eq!(engine.execute("final_score", &[]), Ok("W+3".to_string()));
+        engine.execute("boardsize", &["6"]).unwrap();
+        assert_eq!(engine.game_data.rule_set(), RuleSet::Anti);
+    }
+
+    #[test]
+    fn board_size() {
+        let mut engine = engine();
+        for size in ["3", "27", "x"] {
+            assert_eq!(
+                engine.execute("boardsize", &[size]),
+                Err(EngineError::InvalidSize(size.to_string()))
+            );
+        }
+        assert_eq!(
+            engine.execute("boardsize", &[]),
+            Err(EngineError::MissingArgument)
+        );
+        engine.execute("boardsize", &["6"]).unwrap();
+        let board = engine.execute("showboard", &[]).unwrap();
+        assert!(board.contains("   a b c d e f\n"));
+        assert_eq!(engine.execute("play", &["black", "c2"]), Ok(String::new()));
+        assert_eq!(engine.execute("undo", &[]), Ok(String::new()));
+        assert_eq!(engine.execute("undo", &[]), Err(EngineError::CannotUndo));
+    }
+}
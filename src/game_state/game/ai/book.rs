@@ -0,0 +1,439 @@
+// Opening book of known lines for the standard 8x8 start.
+// Positions are stored in a canonical orientation, so a line is found under all 8 board symmetries.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use bitboard::{BitBoard, Mask, BITBOARD_SIZE};
+use data::{Board, Player};
+use rules::Position;
+
+const CACHE_EXTENSION: &str = "bin";
+const CACHE_MAGIC: &[u8; 4] = b"RVBK";
+const CACHE_VERSION: u8 = 1;
+const CACHE_ENTRY_LEN: usize = 8 + 8 + 1 + 1 + 4;
+
+const SYMMETRY_COUNT: usize = 8;
+
+// Moves and their weights, keyed by canonical position and player to move.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpeningBook {
+    entries: HashMap<(BitBoard, Player), HashMap<board::BoardPosition, u32>>,
+}
+
+#[derive(Debug)]
+pub enum LoadBookError {
+    IO(std::io::Error),
+    Notation(usize, notation::NotationError), // (line number, error)
+    IllegalMove(usize, board::BoardPosition),
+    InvalidCache,
+}
+
+impl std::fmt::Display for LoadBookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadBookError::IO(e) => write!(f, "cannot open opening book: {}", e),
+            LoadBookError::Notation(line, e) => {
+                write!(f, "line {}: cannot read moves: {:?}", line, e)
+            }
+            LoadBookError::IllegalMove(line, pos) => write!(
+                f,
+                "line {}: illegal move {}",
+                line,
+                notation::to_string(pos)
+            ),
+            LoadBookError::InvalidCache => write!(f, "invalid opening book cache"),
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadBookError {
+    fn from(value: std::io::Error) -> Self {
+        LoadBookError::IO(value)
+    }
+}
+
+// (x, y) -> (x, y), (7 - x, y), (x, 7 - y), (7 - x, 7 - y), (y, x), (7 - y, x), (y, 7 - x), (7 - y, 7 - x)
+fn transform(position: &board::BoardPosition, symmetry: usize) -> board::BoardPosition {
+    let last = BITBOARD_SIZE as board::PositionUnit - 1;
+    let (x, y) = match symmetry & 0b100 {
+        0 => (position.x, position.y),
+        _ => (position.y, position.x),
+    };
+    let x = if symmetry & 0b001 != 0 { last - x } else { x };
+    let y = if symmetry & 0b010 != 0 { last - y } else { y };
+    (x, y).into()
+}
+
+fn inverse(symmetry: usize) -> usize {
+    match symmetry {
+        5 => 6,
+        6 => 5,
+        s => s,
+    }
+}
+
+fn transform_mask(mask: Mask, symmetry: usize) -> Mask {
+    bitboard::mask_to_positions(mask)
+        .filter_map(|pos| bitboard::position_to_mask(&transform(&pos, symmetry)))
+        .fold(0, |mask, m| mask | m)
+}
+
+fn transform_board(bit_board: &BitBoard, symmetry: usize) -> BitBoard {
+    BitBoard::new(
+        transform_mask(bit_board.mask(Player::Black), symmetry),
+        transform_mask(bit_board.mask(Player::White), symmetry),
+    )
+}
+
+// The smallest of the 8 orientations and the symmetry which gives it.
+fn canonical(bit_board: &BitBoard) -> (BitBoard, usize) {
+    (0..SYMMETRY_COUNT)
+        .map(|symmetry| (transform_board(bit_board, symmetry), symmetry))
+        .min_by_key(|(b, _)| (b.mask(Player::Black), b.mask(Player::White)))
+        .unwrap()
+}
+
+fn cache_path(path: &str) -> String {
+    format!("{}.{}", path, CACHE_EXTENSION)
+}
+
+// The cache is used only when it was written after the book was last changed.
+fn is_cache_fresh(path: &str, cache_path: &str) -> bool {
+    let modified = |p: &str| std::fs::metadata(p).and_then(|m| m.modified());
+    matches!(
+        (modified(path), modified(cache_path)),
+        (Ok(book), Ok(cache)) if cache >= book
+    )
+}
+
+impl OpeningBook {
+    pub fn start_position() -> BitBoard {
+        let size = board::Size::new(BITBOARD_SIZE, BITBOARD_SIZE).unwrap();
+        let mut board = Board::new(size);
+        rules::set_initial_cells(&mut board);
+        BitBoard::try_from(&board).unwrap()
+    }
+
+    // One line per opening, moves from the standard start with black first, e.g. "f5d6c3d3c4".
+    // An optional weight may follow the moves, 1 by default.
+    // Empty lines and lines starting with '%' or '#' are skipped.
+    pub fn from_text(text: &str) -> Result<Self, LoadBookError> {
+        let mut book = OpeningBook::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('%') || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let moves = notation::parse_sequence(parts.next().unwrap_or_default())
+                .map_err(|e| LoadBookError::Notation(line_number, e))?;
+            let weight = parts.next().and_then(|w| w.parse().ok()).unwrap_or(1);
+
+            let size = board::Size::new(BITBOARD_SIZE, BITBOARD_SIZE).unwrap();
+            let mut bit_board = Self::start_position();
+            let mut player = Player::Black;
+            for m in moves {
+                let position = notation::standard_orientation(&m, size);
+                if !bit_board.has_legal_move(player) {
+                    player = player.next();
+                }
+                book.add(&bit_board, player, &position, weight);
+                bit_board
+                    .apply_move(&position, player)
+                    .map_err(|_| LoadBookError::IllegalMove(line_number, m))?;
+                player = player.next();
+            }
+        }
+        Ok(book)
+    }
+
+    // Reads the binary cache next to the text book, rebuilding it when it's missing or stale.
+    pub fn load(path: &str) -> Result<Self, LoadBookError> {
+        let cache_path = cache_path(path);
+        if is_cache_fresh(path, &cache_path) {
+            if let Ok(book) = std::fs::read(&cache_path)
+                .map_err(LoadBookError::from)
+                .and_then(|bytes| Self::from_bytes(&bytes))
+            {
+                return Ok(book);
+            }
+        }
+
+        let book = Self::from_text(&std::fs::read_to_string(path)?)?;
+        if let Err(e) = std::fs::write(&cache_path, book.to_bytes()) {
+            bevy::prelude::warn!("cannot write opening book cache {} : {}", cache_path, e);
+        }
+        Ok(book)
+    }
+
+    fn add(
+        &mut self,
+        bit_board: &BitBoard,
+        player: Player,
+        position: &board::BoardPosition,
+        weight: u32,
+    ) {
+        let (canonical_board, symmetry) = canonical(bit_board);
+        *self
+            .entries
+            .entry((canonical_board, player))
+            .or_default()
+            .entry(transform(position, symmetry))
+            .or_default() += weight;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Book moves and weights for the position, in its own orientation.
+    pub fn moves(&self, bit_board: &BitBoard, player: Player) -> Vec<(board::BoardPosition, u32)> {
+        let (canonical_board, symmetry) = canonical(bit_board);
+        let mut moves: Vec<_> = self
+            .entries
+            .get(&(canonical_board, player))
+            .into_iter()
+            .flatten()
+            .map(|(pos, weight)| (transform(pos, inverse(symmetry)), *weight))
+            .collect();
+        moves.sort_by_key(|(pos, _)| (pos.y, pos.x));
+        moves
+    }
+
+    pub fn game_data_moves(&self, game_data: &data::GameData) -> Vec<(board::BoardPosition, u32)> {
+        match BitBoard::try_from(game_data.board()) {
+            Ok(bit_board) => self.moves(&bit_board, game_data.current_player()),
+            Err(_) => vec![],
+        }
+    }
+
+    // A book move picked at random in proportion to its weight, None when out of book.
+    pub fn choose_move<R: Rng>(
+        &self,
+        game_data: &data::GameData,
+        rng: &mut R,
+    ) -> Option<board::BoardPosition> {
+        let moves = self.game_data_moves(game_data);
+        let distribution = WeightedIndex::new(moves.iter().map(|(_, weight)| *weight)).ok()?;
+        Some(moves[distribution.sample(rng)].0)
+    }
+
+    // magic, version, entry count, then (black, white, player, move index, weight) per move,
+    // all little endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .flat_map(|((bit_board, player), moves)| {
+                moves.iter().map(move |(pos, weight)| {
+                    let index = bitboard::position_to_mask(pos).unwrap().trailing_zeros() as u8;
+                    (
+                        bit_board.mask(Player::Black),
+                        bit_board.mask(Player::White),
+                        *player as u8,
+                        index,
+                        *weight,
+                    )
+                })
+            })
+            .collect();
+        // the same book always gives the same file
+        entries.sort();
+
+        let mut bytes = Vec::with_capacity(9 + entries.len() * CACHE_ENTRY_LEN);
+        bytes.extend_from_slice(CACHE_MAGIC);
+        bytes.push(CACHE_VERSION);
+        bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for (black, white, player, index, weight) in entries {
+            bytes.extend_from_slice(&black.to_le_bytes());
+            bytes.extend_from_slice(&white.to_le_bytes());
+            bytes.push(player);
+            bytes.push(index);
+            bytes.extend_from_slice(&weight.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LoadBookError> {
+        let header_len = CACHE_MAGIC.len() + 1 + 4;
+        if bytes.len() < header_len
+            || &bytes[..CACHE_MAGIC.len()] != CACHE_MAGIC
+            || bytes[CACHE_MAGIC.len()] != CACHE_VERSION
+        {
+            return Err(LoadBookError::InvalidCache);
+        }
+        let count = u32::from_le_bytes(bytes[5..9].try_into().unwrap()) as usize;
+        let body = &bytes[header_len..];
+        if body.len() != count * CACHE_ENTRY_LEN {
+            return Err(LoadBookError::InvalidCache);
+        }
+
+        let mut book = OpeningBook::default();
+        for entry in body.chunks_exact(CACHE_ENTRY_LEN) {
+            let black = Mask::from_le_bytes(entry[0..8].try_into().unwrap());
+            let white = Mask::from_le_bytes(entry[8..16].try_into().unwrap());
+            let player = match entry[16] {
+                p if p == Player::Black as u8 => Player::Black,
+                p if p == Player::White as u8 => Player::White,
+                _ => return Err(LoadBookError::InvalidCache),
+            };
+            let index = match entry[17] {
+                i if u32::from(i) < Mask::BITS => i,
+                _ => return Err(LoadBookError::InvalidCache),
+            };
+            let pos = bitboard::mask_to_positions(1 << index).next().unwrap();
+            let weight = u32::from_le_bytes(entry[18..22].try_into().unwrap());
+            book.entries
+                .entry((BitBoard::new(black, white), player))
+                .or_default()
+                .insert(pos, weight);
+        }
+        Ok(book)
+    }
+}
+
+// An opening book named by its path in the settings file, loaded once and shared between clones.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BookFile {
+    path: String,
+    book: Arc<OpeningBook>,
+}
+
+impl BookFile {
+    pub fn load(path: &str) -> Result<Self, LoadBookError> {
+        Ok(BookFile {
+            path: path.to_string(),
+            book: Arc::new(OpeningBook::load(path)?),
+        })
+    }
+
+    pub fn book(&self) -> &OpeningBook {
+        &self.book
+    }
+}
+
+impl std::fmt::Debug for BookFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BookFile").field(&self.path).finish()
+    }
+}
+
+impl TryFrom<String> for BookFile {
+    type Error = LoadBookError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        BookFile::load(&value)
+    }
+}
+
+impl From<BookFile> for String {
+    fn from(value: BookFile) -> Self {
+        value.path
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    const BOOK_PATH: &str = "assets/books/openings.txt";
+
+    fn game_data_after(moves: &str) -> data::GameData {
+        let mut game_data = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        for m in notation::parse_sequence(moves).unwrap() {
+            game_data.play(&m).unwrap();
+        }
+        game_data
+    }
+
+    #[test]
+    fn symmetric_lookup() {
+        // f5d6c3 from the standard start is c5e6f3 here
+        let book = OpeningBook::from_text("f5d6c3\n").unwrap();
+        // e3, f4 and d6 are c5 mirrored, the book answers in the same orientation
+        for (first, reply) in [("c5", "e6"), ("e3", "f5"), ("f4", "d3"), ("d6", "c4")] {
+            let game_data = game_data_after(first);
+            let moves = book.game_data_moves(&game_data);
+            assert_eq!(
+                moves,
+                vec![(notation::parse(reply).unwrap(), 1)],
+                "{}",
+                first
+            );
+        }
+        assert_eq!(book.game_data_moves(&game_data_after("c5c6")), vec![]);
+    }
+
+    #[test]
+    fn weights_add_up() {
+        let book = OpeningBook::from_text("% comment\nf5d6 2\nf5f6\nd3c3\n").unwrap();
+        let moves = book.game_data_moves(&game_data_after(""));
+        assert_eq!(moves, vec![((4, 2).into(), 1), ((2, 4).into(), 3)]);
+
+        // d3c3 is f5f6 turned around
+        let mut rng = StdRng::seed_from_u64(0);
+        let replies = book.game_data_moves(&game_data_after("c5"));
+        assert_eq!(replies, vec![((2, 5).into(), 2), ((4, 5).into(), 2)]);
+        let chosen = book.choose_move(&game_data_after("c5"), &mut rng).unwrap();
+        assert!(replies.iter().any(|(pos, _)| *pos == chosen));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            OpeningBook::from_text("f5\nf5f5\n"),
+            Err(LoadBookError::IllegalMove(2, _))
+        ));
+        assert!(matches!(
+            OpeningBook::from_text("f5?"),
+            Err(LoadBookError::Notation(1, _))
+        ));
+        assert!(matches!(
+            OpeningBook::from_bytes(b"RVBK"),
+            Err(LoadBookError::InvalidCache)
+        ));
+
+        // a move index past the last cell
+        let mut bytes = OpeningBook::from_text("f5\n").unwrap().to_bytes();
+        let index = bytes.len() - 5;
+        bytes[index] = 64;
+        assert!(matches!(
+            OpeningBook::from_bytes(&bytes),
+            Err(LoadBookError::InvalidCache)
+        ));
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let text = std::fs::read_to_string(BOOK_PATH).unwrap();
+        let book = OpeningBook::from_text(&text).unwrap();
+        assert!(!book.is_empty());
+        assert_eq!(OpeningBook::from_bytes(&book.to_bytes()).unwrap(), book);
+
+        let dir = std::env::temp_dir().join(format!("reversi_book_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("openings.txt");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &text).unwrap();
+        let loaded = OpeningBook::load(path).unwrap();
+        assert!(std::path::Path::new(&cache_path(path)).exists());
+        assert_eq!(OpeningBook::load(path).unwrap(), loaded);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+// Exact endgame solver, searches every line to the end of the game.
+// Only practical for the last ~20 empty cells.
+use super::*;
+use bitboard::{BitBoard, Mask};
+use data::{Board, Player};
+use rules::{Position, SearchBoard};
+
+const INFINITY: i32 = i32::MAX;
+
+// Below this many empties move ordering costs more than it saves.
+const ORDERING_MIN_EMPTIES: u16 = 7;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EndgameResult {
+    pub best_move: Option<board::BoardPosition>,
+    pub disc_difference: i32, // final own discs minus opposite discs with perfect play, negated in anti-reversi
+    pub nodes: u64,
+}
+
+pub fn solve(
+    search_board: &SearchBoard,
+    player: Player,
+    rule_set: rules::RuleSet,
+) -> EndgameResult {
+    match search_board {
+        SearchBoard::Bit(bit_board) => BitSolver::new(rule_set).solve(bit_board, player),
+        SearchBoard::Map(board) => GenericSolver::new(rule_set).solve(board, player),
+    }
+}
+
+fn disc_difference<P: Position>(position: &P, player: Player, rule_set: rules::RuleSet) -> i32 {
+    let score = position.score();
+    rule_set.objective(score.get(&player) as i32 - score.get(&player.next()) as i32)
+}
+
+struct BitSolver {
+    rule_set: rules::RuleSet,
+    nodes: u64,
+}
+
+impl BitSolver {
+    fn new(rule_set: rules::RuleSet) -> Self {
+        BitSolver { rule_set, nodes: 0 }
+    }
+
+    fn solve(&mut self, bit_board: &BitBoard, player: Player) -> EndgameResult {
+        let moves = self.ordered_moves(bit_board, player);
+        if moves.is_empty() {
+            let disc_difference =
+                -self.negamax(bit_board, player.next(), -INFINITY, INFINITY, true);
+            return EndgameResult {
+                best_move: None,
+                disc_difference,
+                nodes: self.nodes,
+            };
+        }
+
+        let mut alpha = -INFINITY;
+        let mut best_move = None;
+        for (m, child) in moves {
+            let score = -self.negamax(&child, player.next(), -INFINITY, -alpha, false);
+            if best_move.is_none() || score > alpha {
+                alpha = score;
+                best_move = bitboard::mask_to_positions(m).next();
+            }
+        }
+        EndgameResult {
+            best_move,
+            disc_difference: alpha,
+            nodes: self.nodes,
+        }
+    }
+
+    // (move, position after the move), fewest opposite replies first
+    fn ordered_moves(&self, bit_board: &BitBoard, player: Player) -> Vec<(Mask, BitBoard)> {
+        let mut moves = bit_board.legal_moves_mask(player);
+        let mut children = vec![];
+        while moves != 0 {
+            let m = moves & moves.wrapping_neg();
+            moves ^= m;
+            let mut child = *bit_board;
+            child.apply_mask(m, bit_board.flips_mask(m, player), player);
+            children.push((m, child));
+        }
+        if bit_board.count(Player::None) >= ORDERING_MIN_EMPTIES {
+            children.sort_by_key(|(_, child)| child.legal_moves_mask(player.next()).count_ones());
+        }
+        children
+    }
+
+    fn negamax(
+        &mut self,
+        bit_board: &BitBoard,
+        player: Player,
+        mut alpha: i32,
+        beta: i32,
+        passed: bool,
+    ) -> i32 {
+        self.nodes += 1;
+        let moves = self.ordered_moves(bit_board, player);
+        if moves.is_empty() {
+            if passed {
+                return disc_difference(bit_board, player, self.rule_set);
+            }
+            return -self.negamax(bit_board, player.next(), -beta, -alpha, true);
+        }
+
+        let mut best = -INFINITY;
+        for (_, child) in moves {
+            let score = -self.negamax(&child, player.next(), -beta, -alpha, false);
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+// Same search over any board representation, for sizes the bitboard can't hold.
+struct GenericSolver {
+    rule_set: rules::RuleSet,
+    nodes: u64,
+}
+
+impl GenericSolver {
+    fn new(rule_set: rules::RuleSet) -> Self {
+        GenericSolver { rule_set, nodes: 0 }
+    }
+
+    fn solve<P: Position>(&mut self, position: &P, player: Player) -> EndgameResult {
+        let moves = position.legal_moves(player);
+        if moves.is_empty() {
+            let disc_difference = -self.negamax(position, player.next(), -INFINITY, INFINITY, true);
+            return EndgameResult {
+                best_move: None,
+                disc_difference,
+                nodes: self.nodes,
+            };
+        }
+
+        let mut alpha = -INFINITY;
+        let mut best_move = None;
+        for m in moves {
+            let mut child = position.clone();
+            child.apply_move(&m, player).unwrap();
+            let score = -self.negamax(&child, player.next(), -INFINITY, -alpha, false);
+            if best_move.is_none() || score > alpha {
+                alpha = score;
+                best_move = Some(m);
+            }
+        }
+        EndgameResult {
+            best_move,
+            disc_difference: alpha,
+            nodes: self.nodes,
+        }
+    }
+
+    fn negamax<P: Position>(
+        &mut self,
+        position: &P,
+        player: Player,
+        mut alpha: i32,
+        beta: i32,
+        passed: bool,
+    ) -> i32 {
+        self.nodes += 1;
+        let moves = position.legal_moves(player);
+        if moves.is_empty() {
+            if passed {
+                return disc_difference(position, player, self.rule_set);
+            }
+            return -self.negamax(position, player.next(), -beta, -alpha, true);
+        }
+
+        let mut best = -INFINITY;
+        for m in moves {
+            let mut child = position.clone();
+            child.apply_move(&m, player).unwrap();
+            let score = -self.negamax(&child, player.next(), -beta, -alpha, false);
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParsePositionError {
+    MissingPlayer,
+    NotSquare(usize),
+    InvalidCell(char),
+    InvalidPlayer(char),
+}
+
+// One position per line: the cells row by row from a1 as 'X' (black), 'O' (white) or '-',
+// then the player to move, e.g. "---...---XO--- X". Empty lines and lines starting with '%' are skipped.
+pub fn parse_position(line: &str) -> Result<(Board, Player), ParsePositionError> {
+    let mut parts = line.split_whitespace();
+    let cells = parts.next().unwrap_or_default();
+    let player = match parts.next().and_then(|p| p.chars().next()) {
+        Some(c) => parse_cell(c)
+            .ok()
+            .filter(|p| *p != Player::None)
+            .ok_or(ParsePositionError::InvalidPlayer(c))?,
+        None => return Err(ParsePositionError::MissingPlayer),
+    };
+
+    let cell_count = cells.chars().count();
+    let size = (1..=cell_count)
+        .find(|n| n * n >= cell_count)
+        .filter(|n| n * n == cell_count)
+        .ok_or(ParsePositionError::NotSquare(cell_count))?;
+    let mut board = Board::new(board::Size::new(size as u16, size as u16).unwrap());
+    for (index, c) in cells.chars().enumerate() {
+        let pos: board::BoardPosition = ((index % size) as i64, (index / size) as i64).into();
+        *board.cell_mut(&pos).unwrap() = parse_cell(c)?;
+    }
+    Ok((board, player))
+}
+
+fn parse_cell(c: char) -> Result<Player, ParsePositionError> {
+    match c {
+        'X' | 'x' | '*' => Ok(Player::Black),
+        'O' | 'o' => Ok(Player::White),
+        '-' | '.' => Ok(Player::None),
+        c => Err(ParsePositionError::InvalidCell(c)),
+    }
+}
+
+pub fn parse_positions(text: &str) -> Vec<Result<(Board, Player), ParsePositionError>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('%'))
+        .map(parse_position)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    // play random moves from the start until only empties are left
+    fn random_position(rng: &mut StdRng, empties: u16) -> (Board, Player) {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        rules::set_initial_cells(&mut board);
+        let mut player = Player::Black;
+        while board.empty_count() > empties && !board.is_game_over() {
+            let moves = rules::legal_moves(&board, player);
+            if !moves.is_empty() {
+                let m = moves[rng.gen_range(0..moves.len())];
+                rules::apply_move(&mut board, &m, player).unwrap();
+            }
+            player = player.next();
+        }
+        (board, player)
+    }
+
+    #[test]
+    fn bit_solver_matches_generic_solver() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..10 {
+            let (board, player) = random_position(&mut rng, 8);
+            for rule_set in [rules::RuleSet::Standard, rules::RuleSet::Anti] {
+                let bit_result = solve(&SearchBoard::from(&board), player, rule_set);
+                let generic_result = GenericSolver::new(rule_set).solve(&board, player);
+                assert_eq!(bit_result.disc_difference, generic_result.disc_difference);
+            }
+        }
+    }
+
+    #[test]
+    fn best_move_keeps_the_score() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let (board, player) = random_position(&mut rng, 10);
+        let search_board = SearchBoard::from(&board);
+        let result = solve(&search_board, player, rules::RuleSet::Standard);
+        let mut child = search_board.clone();
+        child
+            .apply_move(&result.best_move.unwrap(), player)
+            .unwrap();
+        let child_result = solve(&child, player.next(), rules::RuleSet::Standard);
+        assert_eq!(result.disc_difference, -child_result.disc_difference);
+    }
+
+    #[test]
+    fn anti_reversi_plays_for_fewer_discs() {
+        // (2, 3) flips both white discs and ends the game
+        let mut board = Board::new(board::Size::new(4, 4).unwrap());
+        for (pos, player) in [
+            ((0, 3), Player::Black),
+            ((0, 2), Player::Black),
+            ((0, 1), Player::Black),
+            ((1, 3), Player::White),
+            ((1, 2), Player::White),
+        ] {
+            *board.cell_mut(&pos.into()).unwrap() = player;
+        }
+        let search_board = SearchBoard::from(&board);
+        let standard = solve(&search_board, Player::Black, rules::RuleSet::Standard);
+        assert!(standard.disc_difference >= 5);
+
+        let anti = solve(&search_board, Player::Black, rules::RuleSet::Anti);
+        assert_ne!(anti.best_move, Some((2, 3).into()));
+        assert!(anti.disc_difference > -5);
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let line = format!("{}{}{} O", "XO------", "-".repeat(48), "------OX");
+        let (board, player) = parse_position(&line).unwrap();
+        assert_eq!(player, Player::White);
+        assert_eq!(board.cell_ref(&(1, 0).into()), Some(&Player::White));
+        assert_eq!(board.cell_ref(&(7, 7).into()), Some(&Player::Black));
+        assert_eq!(board.empty_count(), 60);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_position("XO-"),
+            Err(ParsePositionError::MissingPlayer)
+        );
+        assert_eq!(
+            parse_position("XO- X"),
+            Err(ParsePositionError::NotSquare(3))
+        );
+        assert_eq!(
+            parse_position("XO-? X"),
+            Err(ParsePositionError::InvalidCell('?'))
+        );
+        assert_eq!(
+            parse_position("XO-- -"),
+            Err(ParsePositionError::InvalidPlayer('-'))
+        );
+        let positions = parse_positions("% comment\n\nXO-- X\n");
+        assert_eq!(positions.len(), 1);
+        assert!(positions[0].is_ok());
+    }
+}
@@ -0,0 +1,207 @@
+// Heuristic scores of a position, used by the search at its depth limit.
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use data::Player;
+use rules::Position;
+
+pub trait Evaluator {
+    // Higher is better for player.
+    fn evaluate<P: Position>(&self, position: &P, player: Player) -> i32;
+}
+
+// Disc count difference.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Material;
+
+impl Evaluator for Material {
+    fn evaluate<P: Position>(&self, position: &P, player: Player) -> i32 {
+        let score = position.score();
+        score.get(&player) as i32 - score.get(&player.next()) as i32
+    }
+}
+
+// Legal move count difference.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Mobility;
+
+impl Evaluator for Mobility {
+    fn evaluate<P: Position>(&self, position: &P, player: Player) -> i32 {
+        position.legal_moves(player).len() as i32 - position.legal_moves(player.next()).len() as i32
+    }
+}
+
+// Sum of cell weights, indexed as weights[y][x]. Cells outside the table weigh nothing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "WeightTableSource", into = "Vec<Vec<i32>>")]
+pub struct WeightTable {
+    weights: Vec<Vec<i32>>,
+}
+
+// In the settings file a table is either written inline or as a path to a JSON file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WeightTableSource {
+    Inline(Vec<Vec<i32>>),
+    Path(String),
+}
+
+#[derive(Debug)]
+pub enum LoadWeightTableError {
+    IO(std::io::Error),
+    Read(serde_json::Error),
+}
+
+impl std::fmt::Display for LoadWeightTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadWeightTableError::IO(e) => write!(f, "cannot open weight table: {}", e),
+            LoadWeightTableError::Read(e) => write!(f, "cannot read weight table: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadWeightTableError {
+    fn from(value: std::io::Error) -> Self {
+        LoadWeightTableError::IO(value)
+    }
+}
+
+impl From<serde_json::Error> for LoadWeightTableError {
+    fn from(value: serde_json::Error) -> Self {
+        LoadWeightTableError::Read(value)
+    }
+}
+
+impl WeightTable {
+    pub fn new(weights: Vec<Vec<i32>>) -> Self {
+        WeightTable { weights }
+    }
+
+    pub fn load(path: &str) -> Result<Self, LoadWeightTableError> {
+        let file = std::fs::File::open(path)?;
+        let reader = std::io::BufReader::new(file);
+        let weights = serde_json::from_reader(reader)?;
+        Ok(WeightTable { weights })
+    }
+
+    pub fn weight(&self, position: &board::BoardPosition) -> i32 {
+        let (Ok(x), Ok(y)) = (usize::try_from(position.x), usize::try_from(position.y)) else {
+            return 0;
+        };
+        self.weights
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl TryFrom<WeightTableSource> for WeightTable {
+    type Error = LoadWeightTableError;
+
+    fn try_from(value: WeightTableSource) -> Result<Self, Self::Error> {
+        match value {
+            WeightTableSource::Inline(weights) => Ok(WeightTable { weights }),
+            WeightTableSource::Path(path) => WeightTable::load(&path),
+        }
+    }
+}
+
+impl From<WeightTable> for Vec<Vec<i32>> {
+    fn from(value: WeightTable) -> Self {
+        value.weights
+    }
+}
+
+impl Evaluator for WeightTable {
+    fn evaluate<P: Position>(&self, position: &P, player: Player) -> i32 {
+        let size = position.size();
+        let opposite_player = player.next();
+        position_pairs(size.x(), size.y())
+            .into_iter()
+            .map(|pos| {
+                let pos = pos.into();
+                match position.cell(&pos) {
+                    Some(p) if p == player => self.weight(&pos),
+                    Some(p) if p == opposite_player => -self.weight(&pos),
+                    _ => 0,
+                }
+            })
+            .sum()
+    }
+}
+
+// Names the evaluator an AI player uses in the settings file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvaluatorType {
+    #[default]
+    Material,
+    Mobility,
+    Positional(WeightTable),
+}
+
+impl Evaluator for EvaluatorType {
+    fn evaluate<P: Position>(&self, position: &P, player: Player) -> i32 {
+        match self {
+            EvaluatorType::Material => Material.evaluate(position, player),
+            EvaluatorType::Mobility => Mobility.evaluate(position, player),
+            EvaluatorType::Positional(table) => table.evaluate(position, player),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use data::Board;
+
+    const POSITIONAL_PATH: &str = "assets/evaluators/positional.json";
+
+    fn new_board() -> Board {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        rules::set_initial_cells(&mut board);
+        board
+    }
+
+    #[test]
+    fn material() {
+        let mut board = new_board();
+        rules::apply_move(&mut board, &(4, 2).into(), Player::Black).unwrap();
+        assert_eq!(Material.evaluate(&board, Player::Black), 3);
+        assert_eq!(Material.evaluate(&board, Player::White), -3);
+    }
+
+    #[test]
+    fn mobility() {
+        let board = new_board();
+        assert_eq!(Mobility.evaluate(&board, Player::Black), 0);
+    }
+
+    #[test]
+    fn positional_prefers_corner() {
+        let table = WeightTable::load(POSITIONAL_PATH).unwrap();
+        let mut corner = new_board();
+        *corner.cell_mut(&(0, 0).into()).unwrap() = Player::Black;
+        let mut x_square = new_board();
+        *x_square.cell_mut(&(1, 1).into()).unwrap() = Player::Black;
+        assert!(table.evaluate(&corner, Player::Black) > table.evaluate(&x_square, Player::Black));
+        assert_eq!(table.weight(&(-1, 0).into()), 0);
+    }
+
+    #[test]
+    fn evaluator_type_from_settings() {
+        let json = format!(r#"{{"Positional": "{}"}}"#, POSITIONAL_PATH);
+        let evaluator_type: EvaluatorType = serde_json::from_str(&json).unwrap();
+        let table = WeightTable::load(POSITIONAL_PATH).unwrap();
+        assert_eq!(evaluator_type, EvaluatorType::Positional(table));
+
+        // written back inline
+        let json = serde_json::to_string(&evaluator_type).unwrap();
+        let read_back: EvaluatorType = serde_json::from_str(&json).unwrap();
+        assert_eq!(read_back, evaluator_type);
+
+        let evaluator_type: EvaluatorType = serde_json::from_str(r#""Mobility""#).unwrap();
+        assert_eq!(evaluator_type, EvaluatorType::Mobility);
+    }
+}
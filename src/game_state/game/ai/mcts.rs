@@ -0,0 +1,280 @@
+// Monte Carlo tree search with UCT selection, driven only by random playouts.
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use data::Player;
+use rules::Position;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MctsSettings {
+    pub iterations: u32,
+    pub exploration: f32,
+    pub seed: Option<u64>,    // None picks a random seed
+    pub endgame_empties: u16, // solve exactly from this many empty cells, 0 never does
+    pub opening_book: Option<book::BookFile>,
+}
+
+impl Default for MctsSettings {
+    fn default() -> Self {
+        MctsSettings {
+            iterations: 1000,
+            exploration: std::f32::consts::SQRT_2,
+            seed: None,
+            endgame_empties: 0,
+            opening_book: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveStats {
+    pub position: board::BoardPosition,
+    pub visits: u32,
+    pub wins: f32, // a draw counts as half a win
+}
+
+impl MoveStats {
+    pub fn win_rate(&self) -> f32 {
+        if self.visits == 0 {
+            0.
+        } else {
+            self.wins / self.visits as f32
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MctsResult {
+    pub best_move: Option<board::BoardPosition>,
+    pub move_stats: Vec<MoveStats>,
+}
+
+struct Node<P: Position> {
+    position: P,
+    player: Player, // to move, passes are already applied
+    mover: Player,  // played the move leading here, wins are counted for it
+    last_move: Option<board::BoardPosition>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried_moves: Vec<board::BoardPosition>,
+    visits: u32,
+    wins: f32,
+}
+
+impl<P: Position> Node<P> {
+    fn new(
+        position: P,
+        player: Player,
+        last_move: Option<board::BoardPosition>,
+        parent: Option<usize>,
+    ) -> Self {
+        // skip the turn of a player without any legal move
+        let mut player = player;
+        let mut untried_moves = position.legal_moves(player);
+        if untried_moves.is_empty() {
+            let opposite_moves = position.legal_moves(player.next());
+            if !opposite_moves.is_empty() {
+                player = player.next();
+                untried_moves = opposite_moves;
+            }
+        }
+        Node {
+            position,
+            player,
+            mover: Player::None,
+            last_move,
+            parent,
+            children: vec![],
+            untried_moves,
+            visits: 0,
+            wins: 0.,
+        }
+    }
+}
+
+pub struct Mcts {
+    settings: MctsSettings,
+    rule_set: rules::RuleSet,
+    rng: StdRng,
+}
+
+impl Mcts {
+    pub fn new(settings: MctsSettings) -> Self {
+        let rng = match settings.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Mcts {
+            settings,
+            rule_set: rules::RuleSet::default(),
+            rng,
+        }
+    }
+
+    pub fn with_rule_set(mut self, rule_set: rules::RuleSet) -> Self {
+        self.rule_set = rule_set;
+        self
+    }
+
+    pub fn search<P: Position>(&mut self, position: &P, player: Player) -> MctsResult {
+        let mut nodes = vec![Node::new(position.clone(), player, None, None)];
+        if nodes[0].player != player {
+            // the player has to pass, nothing to search
+            return MctsResult::default();
+        }
+
+        for _ in 0..self.settings.iterations {
+            let selected = self.select(&nodes);
+            let expanded = self.expand(&mut nodes, selected);
+            let winner = self.playout(&nodes[expanded].position, nodes[expanded].player);
+            Self::backpropagate(&mut nodes, expanded, winner);
+        }
+
+        let move_stats: Vec<MoveStats> = nodes[0]
+            .children
+            .iter()
+            .map(|&child| MoveStats {
+                position: nodes[child].last_move.unwrap(),
+                visits: nodes[child].visits,
+                wins: nodes[child].wins,
+            })
+            .collect();
+        let best_move = move_stats
+            .iter()
+            .max_by_key(|stats| stats.visits)
+            .map(|stats| stats.position)
+            .or_else(|| nodes[0].untried_moves.first().copied());
+        MctsResult {
+            best_move,
+            move_stats,
+        }
+    }
+
+    fn select<P: Position>(&self, nodes: &[Node<P>]) -> usize {
+        let mut index = 0;
+        while nodes[index].untried_moves.is_empty() && !nodes[index].children.is_empty() {
+            let parent_visits = nodes[index].visits as f32;
+            index = *nodes[index]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    let a = self.uct(&nodes[a], parent_visits);
+                    let b = self.uct(&nodes[b], parent_visits);
+                    a.total_cmp(&b)
+                })
+                .unwrap();
+        }
+        index
+    }
+
+    fn uct<P: Position>(&self, node: &Node<P>, parent_visits: f32) -> f32 {
+        let visits = node.visits as f32;
+        node.wins / visits + self.settings.exploration * (parent_visits.ln() / visits).sqrt()
+    }
+
+    fn expand<P: Position>(&mut self, nodes: &mut Vec<Node<P>>, index: usize) -> usize {
+        if nodes[index].untried_moves.is_empty() {
+            return index; // game over
+        }
+        let move_index = self.rng.gen_range(0..nodes[index].untried_moves.len());
+        let m = nodes[index].untried_moves.swap_remove(move_index);
+        let mover = nodes[index].player;
+        let mut position = nodes[index].position.clone();
+        position.apply_move(&m, mover).unwrap();
+
+        let mut child = Node::new(position, mover.next(), Some(m), Some(index));
+        child.mover = mover;
+        nodes.push(child);
+        let child_index = nodes.len() - 1;
+        nodes[index].children.push(child_index);
+        child_index
+    }
+
+    // Random moves until the game ends, returns the winner or Player::None for a draw.
+    fn playout<P: Position>(&mut self, position: &P, player: Player) -> Player {
+        let mut position = position.clone();
+        let mut player = player;
+        let mut passed = false;
+        loop {
+            let moves = position.legal_moves(player);
+            if moves.is_empty() {
+                if passed {
+                    return self.rule_set.winner(&position.score());
+                }
+                passed = true;
+            } else {
+                let m = moves[self.rng.gen_range(0..moves.len())];
+                position.apply_move(&m, player).unwrap();
+                passed = false;
+            }
+            player = player.next();
+        }
+    }
+
+    fn backpropagate<P: Position>(nodes: &mut [Node<P>], index: usize, winner: Player) {
+        let mut index = Some(index);
+        while let Some(i) = index {
+            let node = &mut nodes[i];
+            node.visits += 1;
+            if winner == Player::None {
+                node.wins += 0.5;
+            } else if winner == node.mover {
+                node.wins += 1.;
+            }
+            index = node.parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use data::Board;
+
+    fn settings(iterations: u32) -> MctsSettings {
+        MctsSettings {
+            iterations,
+            seed: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reproducible_with_seed() {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        rules::set_initial_cells(&mut board);
+        let search_board = rules::SearchBoard::from(&board);
+        let first = Mcts::new(settings(200)).search(&search_board, Player::Black);
+        let second = Mcts::new(settings(200)).search(&search_board, Player::Black);
+        assert_eq!(first, second);
+
+        let visits: u32 = first.move_stats.iter().map(|stats| stats.visits).sum();
+        assert_eq!(visits, 200);
+        assert_eq!(first.move_stats.len(), 4);
+    }
+
+    #[test]
+    fn takes_winning_move() {
+        // (2, 7) flips both white discs and ends the game
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        for (pos, player) in [
+            ((0, 7), Player::Black),
+            ((0, 6), Player::Black),
+            ((0, 5), Player::Black),
+            ((1, 7), Player::White),
+            ((1, 6), Player::White),
+        ] {
+            *board.cell_mut(&pos.into()).unwrap() = player;
+        }
+        let result = Mcts::new(settings(500)).search(&board, Player::Black);
+        assert_eq!(result.best_move, Some((2, 7).into()));
+        let best = result
+            .move_stats
+            .iter()
+            .find(|stats| stats.position == (2, 7).into())
+            .unwrap();
+        assert_eq!(best.win_rate(), 1.);
+    }
+}
@@ -0,0 +1,156 @@
+// Computer players, they only read data::GameData and never touch the Bevy world.
+pub mod book;
+pub mod endgame;
+pub mod evaluator;
+pub mod mcts;
+pub mod search;
+
+use super::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum PlayerController {
+    #[default]
+    Human,
+    AlphaBeta(search::SearchSettings),
+    Mcts(mcts::MctsSettings),
+}
+
+impl PlayerController {
+    pub fn is_human(&self) -> bool {
+        matches!(self, PlayerController::Human)
+    }
+
+    pub fn endgame_empties(&self) -> u16 {
+        match self {
+            PlayerController::Human => 0,
+            PlayerController::AlphaBeta(settings) => settings.endgame_empties,
+            PlayerController::Mcts(settings) => settings.endgame_empties,
+        }
+    }
+
+    pub fn opening_book(&self) -> Option<&book::OpeningBook> {
+        let book_file = match self {
+            PlayerController::Human => None,
+            PlayerController::AlphaBeta(settings) => settings.opening_book.as_ref(),
+            PlayerController::Mcts(settings) => settings.opening_book.as_ref(),
+        };
+        book_file.map(book::BookFile::book)
+    }
+
+    // None for humans, or when the current player has no legal move.
+    pub fn choose_move(&self, game_data: &data::GameData) -> Option<board::BoardPosition> {
+        use rules::Position;
+
+        // book lines are played for the most discs
+        let rule_set = game_data.rule_set();
+        if let Some(position) = self
+            .opening_book()
+            .filter(|_| rule_set == rules::RuleSet::Standard)
+            .and_then(|book| book.choose_move(game_data, &mut rand::thread_rng()))
+        {
+            bevy::prelude::info!("book move : {}", notation::to_string(&position));
+            return Some(position);
+        }
+
+        let search_board = game_data.search_board();
+        let player = game_data.current_player();
+        if !self.is_human() && search_board.empty_count() <= self.endgame_empties() {
+            let result = endgame::solve(&search_board, player, rule_set);
+            bevy::prelude::info!("endgame solver : {:?}", &result);
+            return result.best_move;
+        }
+
+        match self {
+            PlayerController::Human => None,
+            PlayerController::AlphaBeta(settings) => {
+                let result = search::AlphaBeta::new(settings.clone())
+                    .with_rule_set(rule_set)
+                    .search(&search_board, player);
+                bevy::prelude::info!("alpha-beta search : {:?}", &result);
+                result.best_move
+            }
+            PlayerController::Mcts(settings) => {
+                let result = mcts::Mcts::new(settings.clone())
+                    .with_rule_set(rule_set)
+                    .search(&search_board, player);
+                for stats in result.move_stats.iter() {
+                    bevy::prelude::info!(
+                        "mcts move {:?} : visits {}, win rate {:.3}",
+                        stats.position,
+                        stats.visits,
+                        stats.win_rate()
+                    );
+                }
+                result.best_move
+            }
+        }
+    }
+}
+
+// How deep the hint overlay looks at each legal move.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HintSettings {
+    pub depth: u8,
+    pub evaluator: evaluator::EvaluatorType,
+}
+
+impl Default for HintSettings {
+    fn default() -> Self {
+        HintSettings {
+            depth: 3,
+            evaluator: evaluator::EvaluatorType::default(),
+        }
+    }
+}
+
+// Score of every legal move for the current player, higher is better.
+pub fn hint_scores(
+    game_data: &data::GameData,
+    settings: &HintSettings,
+) -> Vec<(board::BoardPosition, i32)> {
+    let search_settings = search::SearchSettings {
+        max_depth: settings.depth,
+        evaluator: settings.evaluator.clone(),
+        ..Default::default()
+    };
+    search::AlphaBeta::new(search_settings)
+        .with_rule_set(game_data.rule_set())
+        .score_moves(&game_data.search_board(), game_data.current_player())
+}
+
+// Turns without a controller are played by humans.
+pub fn is_human(
+    player_controllers: &HashMap<data::Turn, PlayerController>,
+    turn: &data::Turn,
+) -> bool {
+    match player_controllers.get(turn) {
+        Some(player_controller) => player_controller.is_human(),
+        None => true,
+    }
+}
+
+// Undo or redo over the computer's moves, back to where a human decides.
+// Returns false when there was nothing to undo or redo.
+pub fn apply_history_command(
+    game_data: &mut data::GameData,
+    player_controllers: &HashMap<data::Turn, PlayerController>,
+    command: data::HistoryCommand,
+) -> bool {
+    let any_human = [data::Turn::Black, data::Turn::White]
+        .iter()
+        .any(|turn| is_human(player_controllers, turn));
+    let mut changed = false;
+    loop {
+        let done = match command {
+            data::HistoryCommand::Undo => game_data.undo(),
+            data::HistoryCommand::Redo => game_data.redo(),
+        };
+        changed |= done;
+        if !done || !any_human || is_human(player_controllers, game_data.turn()) {
+            return changed;
+        }
+    }
+}
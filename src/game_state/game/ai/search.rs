@@ -0,0 +1,282 @@
+// Negamax with alpha-beta pruning and iterative deepening, bounded by depth and time.
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use data::Player;
+use evaluator::{Evaluator, EvaluatorType};
+use rules::Position;
+
+const INFINITY: i32 = i32::MAX;
+const WIN_SCORE: i32 = 10_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchSettings {
+    pub max_depth: u8,
+    pub time_budget: Duration,
+    pub evaluator: EvaluatorType,
+    pub endgame_empties: u16, // solve exactly from this many empty cells
+    pub opening_book: Option<book::BookFile>,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        SearchSettings {
+            max_depth: 6,
+            time_budget: Duration::from_secs(1),
+            evaluator: EvaluatorType::default(),
+            endgame_empties: 12,
+            opening_book: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Option<board::BoardPosition>,
+    pub score: i32,
+    pub depth: u8, // last fully searched depth
+    pub nodes: u64,
+}
+
+pub struct AlphaBeta {
+    settings: SearchSettings,
+    rule_set: rules::RuleSet,
+    deadline: Instant,
+    nodes: u64,
+}
+
+impl AlphaBeta {
+    pub fn new(settings: SearchSettings) -> Self {
+        AlphaBeta {
+            settings,
+            rule_set: rules::RuleSet::default(),
+            deadline: Instant::now(),
+            nodes: 0,
+        }
+    }
+
+    pub fn with_rule_set(mut self, rule_set: rules::RuleSet) -> Self {
+        self.rule_set = rule_set;
+        self
+    }
+
+    pub fn search<P: Position>(&mut self, position: &P, player: Player) -> SearchResult {
+        self.deadline = Instant::now() + self.settings.time_budget;
+        self.nodes = 0;
+
+        let mut moves = position.legal_moves(player);
+        let mut result = SearchResult {
+            best_move: moves.first().copied(),
+            ..Default::default()
+        };
+        if moves.len() <= 1 {
+            return result;
+        }
+
+        for depth in 1..=self.settings.max_depth {
+            // an unfinished iteration is thrown away, the previous one is kept
+            let Some((best_move, score)) = self.search_root(position, player, &moves, depth) else {
+                break;
+            };
+            result.best_move = Some(best_move);
+            result.score = score;
+            result.depth = depth;
+
+            // search the best move first on the next iteration
+            let index = moves.iter().position(|m| *m == best_move).unwrap();
+            let best_move = moves.remove(index);
+            moves.insert(0, best_move);
+        }
+        result.nodes = self.nodes;
+        result
+    }
+
+    // Every legal move with its score, searched max_depth plies deep with a full window.
+    // Moves still unscored when the time budget runs out are left out.
+    pub fn score_moves<P: Position>(
+        &mut self,
+        position: &P,
+        player: Player,
+    ) -> Vec<(board::BoardPosition, i32)> {
+        self.deadline = Instant::now() + self.settings.time_budget;
+        self.nodes = 0;
+
+        let depth = self.settings.max_depth.max(1);
+        position
+            .legal_moves(player)
+            .into_iter()
+            .filter_map(|m| {
+                let mut child = position.clone();
+                child.apply_move(&m, player).ok()?;
+                let score = -self.negamax(&child, player.next(), depth - 1, -INFINITY, INFINITY)?;
+                Some((m, score))
+            })
+            .collect()
+    }
+
+    fn search_root<P: Position>(
+        &mut self,
+        position: &P,
+        player: Player,
+        moves: &[board::BoardPosition],
+        depth: u8,
+    ) -> Option<(board::BoardPosition, i32)> {
+        let mut alpha = -INFINITY;
+        let mut best = None;
+        for m in moves {
+            let mut child = position.clone();
+            child.apply_move(m, player).ok()?;
+            let score = -self.negamax(&child, player.next(), depth - 1, -INFINITY, -alpha)?;
+            if best.is_none() || score > alpha {
+                alpha = score;
+                best = Some((*m, score));
+            }
+        }
+        best
+    }
+
+    // None when the time budget ran out.
+    fn negamax<P: Position>(
+        &mut self,
+        position: &P,
+        player: Player,
+        depth: u8,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if Instant::now() >= self.deadline {
+            return None;
+        }
+
+        let moves = position.legal_moves(player);
+        if moves.is_empty() {
+            if !position.has_legal_move(player.next()) {
+                return Some(final_score(position, player, self.rule_set));
+            }
+            if depth == 0 {
+                return Some(self.evaluate(position, player));
+            }
+            // pass
+            return Some(-self.negamax(position, player.next(), depth - 1, -beta, -alpha)?);
+        }
+        if depth == 0 {
+            return Some(self.evaluate(position, player));
+        }
+
+        let mut best = -INFINITY;
+        for m in moves {
+            let mut child = position.clone();
+            child.apply_move(&m, player).ok()?;
+            let score = -self.negamax(&child, player.next(), depth - 1, -beta, -alpha)?;
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    // The evaluators favour discs, anti-reversi turns their scores around.
+    fn evaluate<P: Position>(&self, position: &P, player: Player) -> i32 {
+        self.rule_set
+            .objective(self.settings.evaluator.evaluate(position, player))
+    }
+}
+
+// Any win is better than any heuristic score, bigger wins are better.
+fn final_score<P: Position>(position: &P, player: Player, rule_set: rules::RuleSet) -> i32 {
+    let difference = rule_set.objective(evaluator::Material.evaluate(position, player));
+    difference.signum() * WIN_SCORE + difference
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use data::Board;
+
+    fn board_with(cells: &[((board::PositionUnit, board::PositionUnit), Player)]) -> Board {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        for (pos, player) in cells {
+            *board.cell_mut(&(*pos).into()).unwrap() = *player;
+        }
+        board
+    }
+
+    #[test]
+    fn takes_winning_move() {
+        // (2, 7) flips both white discs and ends the game, the other moves flip only one
+        let board = board_with(&[
+            ((0, 7), Player::Black),
+            ((0, 6), Player::Black),
+            ((0, 5), Player::Black),
+            ((1, 7), Player::White),
+            ((1, 6), Player::White),
+        ]);
+        let search_board = rules::SearchBoard::from(&board);
+        let settings = SearchSettings {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let result = AlphaBeta::new(settings).search(&search_board, Player::Black);
+        assert_eq!(result.best_move, Some((2, 7).into()));
+        assert!(result.score > WIN_SCORE);
+
+        // fewer discs win in anti-reversi, taking every white disc loses
+        let result = AlphaBeta::new(SearchSettings {
+            max_depth: 1,
+            ..Default::default()
+        })
+        .with_rule_set(rules::RuleSet::Anti)
+        .search(&search_board, Player::Black);
+        assert_ne!(result.best_move, Some((2, 7).into()));
+        assert!(result.score > -WIN_SCORE);
+    }
+
+    #[test]
+    fn scores_every_move() {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        rules::set_initial_cells(&mut board);
+        rules::apply_move(&mut board, &(2, 4).into(), Player::Black).unwrap();
+        let settings = SearchSettings {
+            max_depth: 2,
+            ..Default::default()
+        };
+        let scores = AlphaBeta::new(settings).score_moves(&board, Player::White);
+        let moves: Vec<_> = scores.iter().map(|(m, _)| *m).collect();
+        assert_eq!(moves, rules::legal_moves(&board, Player::White));
+        // the best score is the one the search picks
+        let best_score = scores.iter().map(|(_, score)| *score).max().unwrap();
+        let result = AlphaBeta::new(SearchSettings {
+            max_depth: 2,
+            ..Default::default()
+        })
+        .search(&board, Player::White);
+        assert_eq!(result.score, best_score);
+    }
+
+    #[test]
+    fn respects_time_budget() {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        rules::set_initial_cells(&mut board);
+        let settings = SearchSettings {
+            max_depth: 60,
+            time_budget: Duration::from_millis(50),
+            evaluator: EvaluatorType::Mobility,
+            endgame_empties: 0,
+            opening_book: None,
+        };
+        let started = Instant::now();
+        let result = AlphaBeta::new(settings).search(&board, Player::Black);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(rules::is_legal_move(
+            &board,
+            &result.best_move.unwrap(),
+            Player::Black
+        ));
+    }
+}
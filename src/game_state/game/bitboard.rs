@@ -0,0 +1,317 @@
+// 8x8 board as two u64 masks, one bit per cell at index y * 8 + x.
+// Moves and flips are computed with shifts, which is much faster than walking data::Board.
+use super::*;
+use data::{Board, Player};
+use rules::{MoveError, MoveResult, Score};
+
+pub const BITBOARD_SIZE: u16 = 8;
+
+pub type Mask = u64;
+
+const NOT_FILE_FIRST: Mask = 0xfefe_fefe_fefe_fefe; // every cell except x == 0
+const NOT_FILE_LAST: Mask = 0x7f7f_7f7f_7f7f_7f7f; // every cell except x == 7
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BitBoard {
+    black: Mask,
+    white: Mask,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BitBoardError {
+    UnsupportedSize(board::Size),
+    BlockedCells, // every bit is a cell, there are no holes
+}
+
+fn shift(mask: Mask, direction: &board::Direction) -> Mask {
+    use board::Direction::*;
+    match direction {
+        Right => (mask << 1) & NOT_FILE_FIRST,
+        Left => (mask >> 1) & NOT_FILE_LAST,
+        Up => mask << 8,
+        Down => mask >> 8,
+        UpRight => (mask << 9) & NOT_FILE_FIRST,
+        UpLeft => (mask << 7) & NOT_FILE_LAST,
+        DownRight => (mask >> 7) & NOT_FILE_FIRST,
+        DownLeft => (mask >> 9) & NOT_FILE_LAST,
+    }
+}
+
+pub fn position_to_mask(position: &board::BoardPosition) -> Option<Mask> {
+    let range = 0..BITBOARD_SIZE as board::PositionUnit;
+    if range.contains(&position.x) && range.contains(&position.y) {
+        Some(1 << (position.y * BITBOARD_SIZE as board::PositionUnit + position.x))
+    } else {
+        None
+    }
+}
+
+pub fn mask_to_positions(mask: Mask) -> impl Iterator<Item = board::BoardPosition> {
+    let mut mask = mask;
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let index = mask.trailing_zeros() as board::PositionUnit;
+        mask &= mask - 1;
+        let size = BITBOARD_SIZE as board::PositionUnit;
+        Some((index % size, index / size).into())
+    })
+}
+
+impl BitBoard {
+    pub fn new(black: Mask, white: Mask) -> Self {
+        BitBoard { black, white }
+    }
+
+    pub fn mask(&self, player: Player) -> Mask {
+        match player {
+            Player::Black => self.black,
+            Player::White => self.white,
+            Player::None => !(self.black | self.white),
+        }
+    }
+
+    // (own, opposite) masks for the player
+    fn masks(&self, player: Player) -> (Mask, Mask) {
+        match player {
+            Player::White => (self.white, self.black),
+            _ => (self.black, self.white),
+        }
+    }
+
+    pub fn legal_moves_mask(&self, player: Player) -> Mask {
+        if player == Player::None {
+            return 0;
+        }
+        let (own, opposite) = self.masks(player);
+        let empty = !(own | opposite);
+        board::DIRECTIONS.iter().fold(0, |moves, direction| {
+            // at most 6 opposite cells can lie between the placed disc and the own one
+            let mut candidates = shift(own, direction) & opposite;
+            for _ in 0..5 {
+                candidates |= shift(candidates, direction) & opposite;
+            }
+            moves | (shift(candidates, direction) & empty)
+        })
+    }
+
+    pub fn flips_mask(&self, position_mask: Mask, player: Player) -> Mask {
+        if player == Player::None || position_mask & (self.black | self.white) != 0 {
+            return 0;
+        }
+        let (own, opposite) = self.masks(player);
+        board::DIRECTIONS.iter().fold(0, |flipped, direction| {
+            let mut line = 0;
+            let mut cursor = shift(position_mask, direction);
+            while cursor & opposite != 0 {
+                line |= cursor;
+                cursor = shift(cursor, direction);
+            }
+            if cursor & own != 0 {
+                flipped | line
+            } else {
+                flipped
+            }
+        })
+    }
+
+    // Place a disc without any legality check, flipped is what flips_mask returned.
+    pub fn apply_mask(&mut self, position_mask: Mask, flipped: Mask, player: Player) {
+        match player {
+            Player::Black => {
+                self.black |= position_mask | flipped;
+                self.white &= !flipped;
+            }
+            Player::White => {
+                self.white |= position_mask | flipped;
+                self.black &= !flipped;
+            }
+            Player::None => (),
+        }
+    }
+
+    pub fn count(&self, player: Player) -> u16 {
+        self.mask(player).count_ones() as u16
+    }
+}
+
+impl TryFrom<&Board> for BitBoard {
+    type Error = BitBoardError;
+
+    fn try_from(board: &Board) -> Result<Self, Self::Error> {
+        if board.size.x() != BITBOARD_SIZE || board.size.y() != BITBOARD_SIZE {
+            return Err(BitBoardError::UnsupportedSize(board.size));
+        }
+        if board.cells.len() != (BITBOARD_SIZE * BITBOARD_SIZE) as usize {
+            return Err(BitBoardError::BlockedCells);
+        }
+        let bit_board =
+            board
+                .cells
+                .iter()
+                .fold(BitBoard::default(), |mut bit_board, (pos, player)| {
+                    let mask = position_to_mask(pos).unwrap();
+                    match player {
+                        Player::Black => bit_board.black |= mask,
+                        Player::White => bit_board.white |= mask,
+                        Player::None => (),
+                    }
+                    bit_board
+                });
+        Ok(bit_board)
+    }
+}
+
+impl From<&BitBoard> for Board {
+    fn from(bit_board: &BitBoard) -> Self {
+        let size = board::Size::new(BITBOARD_SIZE, BITBOARD_SIZE).unwrap();
+        let mut board = Board::new(size);
+        for player in [Player::Black, Player::White] {
+            for pos in mask_to_positions(bit_board.mask(player)) {
+                *board.cell_mut(&pos).unwrap() = player;
+            }
+        }
+        board
+    }
+}
+
+impl rules::Position for BitBoard {
+    fn size(&self) -> board::Size {
+        board::Size::new(BITBOARD_SIZE, BITBOARD_SIZE).unwrap()
+    }
+
+    fn cell(&self, position: &board::BoardPosition) -> Option<Player> {
+        let mask = position_to_mask(position)?;
+        let player = if self.black & mask != 0 {
+            Player::Black
+        } else if self.white & mask != 0 {
+            Player::White
+        } else {
+            Player::None
+        };
+        Some(player)
+    }
+
+    fn legal_moves(&self, player: Player) -> Vec<board::BoardPosition> {
+        mask_to_positions(self.legal_moves_mask(player)).collect()
+    }
+
+    fn has_legal_move(&self, player: Player) -> bool {
+        self.legal_moves_mask(player) != 0
+    }
+
+    fn apply_move(&mut self, position: &board::BoardPosition, player: Player) -> MoveResult {
+        if player == Player::None {
+            return Err(MoveError::NoPlayer);
+        }
+        let mask = position_to_mask(position).ok_or(MoveError::OutOfBoard(*position))?;
+        if mask & (self.black | self.white) != 0 {
+            return Err(MoveError::Occupied(*position));
+        }
+        let flipped = self.flips_mask(mask, player);
+        if flipped == 0 {
+            return Err(MoveError::NoFlip(*position));
+        }
+        self.apply_mask(mask, flipped, player);
+        Ok(mask_to_positions(flipped).collect())
+    }
+
+    fn score(&self) -> Score {
+        Score {
+            black: self.count(Player::Black),
+            white: self.count(Player::White),
+        }
+    }
+
+    fn empty_count(&self) -> u16 {
+        self.count(Player::None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use rules::Position;
+
+    use super::*;
+
+    fn new_board() -> Board {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        rules::set_initial_cells(&mut board);
+        board
+    }
+
+    fn perft(bit_board: &BitBoard, player: Player, depth: u8, passed: bool) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = bit_board.legal_moves_mask(player);
+        if moves == 0 {
+            if passed {
+                return 1;
+            }
+            return perft(bit_board, player.next(), depth - 1, true);
+        }
+        mask_to_positions(moves)
+            .map(|pos| {
+                let mut next = *bit_board;
+                next.apply_move(&pos, player).unwrap();
+                perft(&next, player.next(), depth - 1, false)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn convert_round_trip() {
+        let board = new_board();
+        let bit_board = BitBoard::try_from(&board).unwrap();
+        assert_eq!(bit_board.count(Player::Black), 2);
+        assert_eq!(bit_board.count(Player::White), 2);
+        assert_eq!(Board::from(&bit_board), board);
+    }
+
+    #[test]
+    fn unsupported_size() {
+        let board = Board::new(board::Size::new(6, 6).unwrap());
+        assert_eq!(
+            BitBoard::try_from(&board),
+            Err(BitBoardError::UnsupportedSize(board.size))
+        );
+    }
+
+    #[test]
+    fn perft_from_start() {
+        let bit_board = BitBoard::try_from(&new_board()).unwrap();
+        let counts: Vec<u64> = (1..=6)
+            .map(|depth| perft(&bit_board, Player::Black, depth, false))
+            .collect();
+        assert_eq!(counts, vec![4, 12, 56, 244, 1396, 8200]);
+    }
+
+    #[test]
+    fn same_as_board() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let mut board = new_board();
+            let mut bit_board = BitBoard::try_from(&board).unwrap();
+            let mut player = Player::Black;
+            while !rules::is_game_over(&board) {
+                let mut bit_moves = Position::legal_moves(&bit_board, player);
+                bit_moves.sort_by_key(|pos| (pos.y, pos.x));
+                let moves = rules::legal_moves(&board, player);
+                assert_eq!(bit_moves, moves);
+
+                if let Some(pos) = moves.get(rng.gen_range(0..moves.len().max(1))) {
+                    let mut flipped = rules::apply_move(&mut board, pos, player).unwrap();
+                    let mut bit_flipped = bit_board.apply_move(pos, player).unwrap();
+                    flipped.sort_by_key(|pos| (pos.y, pos.x));
+                    bit_flipped.sort_by_key(|pos| (pos.y, pos.x));
+                    assert_eq!(bit_flipped, flipped);
+                }
+                assert_eq!(Board::from(&bit_board), board);
+                player = player.next();
+            }
+        }
+    }
+}
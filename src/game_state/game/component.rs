@@ -1,6 +1,6 @@
-use bevy::prelude::{Component, Deref, DerefMut};
+use bevy::prelude::{Color, Component, Deref, DerefMut, Timer};
 
-use super::{board, data};
+use super::{board, data, replay};
 
 #[derive(Component, Deref)]
 pub struct BoardPosition(pub board::BoardPosition);
@@ -11,8 +11,60 @@ pub struct BoardParent;
 #[derive(Component)]
 pub struct Cell;
 
-#[derive(Component, Deref, DerefMut)]
+#[derive(Component, Deref, DerefMut, PartialEq, Eq)]
 pub struct Clickable(pub bool);
 
+// The disc would be flipped by the hovered move.
+#[derive(Component, Deref, DerefMut, PartialEq, Eq)]
+pub struct FlipPreview(pub bool);
+
 #[derive(Component, Deref, DerefMut, Debug)]
 pub struct Player(pub data::Player);
+
+#[derive(Component, Deref)]
+pub struct HistoryButton(pub data::HistoryCommand);
+
+// Score of the move on the cell, empty while hints are off.
+#[derive(Component)]
+pub struct HintText;
+
+// Color change of a flipped disc, waiting longer the farther it is from the placed disc.
+#[derive(Component)]
+pub struct FlipAnimation {
+    pub from: Color,
+    pub delay: Timer,
+    pub timer: Timer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HudField {
+    Score(data::Player),
+    Turn,
+    TurnCount,
+    LastMove,
+    Notice,
+}
+
+#[derive(Component, Deref)]
+pub struct HudText(pub HudField);
+
+// The played moves, scrolled by the mouse wheel.
+#[derive(Component, Default)]
+pub struct MoveList {
+    pub scroll: f32,
+}
+
+// The index of the entry's move in the history.
+#[derive(Component, Deref)]
+pub struct MoveListEntry(pub usize);
+
+// Leaves the review for the live game.
+#[derive(Component)]
+pub struct LiveButton;
+
+#[derive(Component, Deref)]
+pub struct ReplayButton(pub replay::Control);
+
+// Whether the replay plays, its speed and how far it is.
+#[derive(Component)]
+pub struct ReplayStatus;
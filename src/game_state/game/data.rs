@@ -5,12 +5,17 @@ use bevy::{
 use serde::{Deserialize, Serialize};
 
 use super::*;
-use std::{ops::Rem, time::Duration};
+use std::time::Duration;
 
 pub type BoardCell = data::Player;
 pub type Board = board::Board<BoardCell>;
 
+pub const MIN_BOARD_SIZE: u16 = 4; // the 2x2 start and a free cell on each side of it
+pub const MAX_BOARD_SIZE: u16 = 26; // one notation letter per column
+
+// The cell count along one side, sides may differ and be odd.
 #[derive(Clone, Copy, Debug, Deref, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
 pub struct BoardSize(u16);
 
 impl BoardSize {
@@ -25,13 +30,34 @@ impl From<BoardSize> for u16 {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardSizeError {
+    TooSmall(u16),
+    TooLarge(u16),
+}
+
+impl std::fmt::Display for BoardSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let size = match self {
+            BoardSizeError::TooSmall(size) | BoardSizeError::TooLarge(size) => size,
+        };
+        write!(
+            f,
+            "unsupported board side of {} cells: sides below {} or above {} are unsupported, \
+             any other width and height can be combined, odd or even",
+            size, MIN_BOARD_SIZE, MAX_BOARD_SIZE
+        )
+    }
+}
+
 impl TryFrom<u16> for BoardSize {
-    type Error = &'static str;
+    type Error = BoardSizeError;
 
     fn try_from(value: u16) -> Result<Self, Self::Error> {
-        match value.rem(2) {
-            0 => Ok(BoardSize(value)),
-            _ => Err("BoardSize can only be even."),
+        match value {
+            _ if value < MIN_BOARD_SIZE => Err(BoardSizeError::TooSmall(value)),
+            _ if value > MAX_BOARD_SIZE => Err(BoardSizeError::TooLarge(value)),
+            _ => Ok(BoardSize(value)),
         }
     }
 }
@@ -45,6 +71,40 @@ pub struct BoardSettings {
     cell_color_background: Color,
     board_player_color_map: HashMap<Player, Color>,
     board_player_color_change_duration: Duration,
+    #[serde(default = "default_cell_color_hint")]
+    cell_color_hint: Color, // the best move, worse moves fade to cell_color_clickable
+    #[serde(default = "default_cell_color_preview")]
+    cell_color_preview: Color, // discs the hovered move would flip
+    #[serde(default = "default_flip_animation_duration")]
+    flip_animation_duration: Duration,
+    #[serde(default = "default_flip_animation_stagger")]
+    flip_animation_stagger: Duration, // added per cell away from the placed disc
+    #[serde(default)]
+    initial_layout: layout::InitialLayout,
+    #[serde(default)]
+    blocked_cells: Vec<String>, // holes in standard notation like "a1", they hold no disc and stop flips
+    #[serde(default = "default_cell_color_blocked")]
+    cell_color_blocked: Color,
+}
+
+fn default_cell_color_hint() -> Color {
+    Color::YELLOW
+}
+
+fn default_cell_color_preview() -> Color {
+    Color::ORANGE
+}
+
+fn default_cell_color_blocked() -> Color {
+    Color::DARK_GRAY
+}
+
+fn default_flip_animation_duration() -> Duration {
+    Duration::from_millis(300)
+}
+
+fn default_flip_animation_stagger() -> Duration {
+    Duration::from_millis(80)
 }
 
 impl BoardSettings {
@@ -66,9 +126,27 @@ impl BoardSettings {
             cell_color_background,
             board_player_color_map: board_player_colors.collect::<HashMap<_, _>>(),
             board_player_color_change_duration,
+            cell_color_hint: default_cell_color_hint(),
+            cell_color_preview: default_cell_color_preview(),
+            flip_animation_duration: default_flip_animation_duration(),
+            flip_animation_stagger: default_flip_animation_stagger(),
+            initial_layout: layout::InitialLayout::default(),
+            blocked_cells: vec![],
+            cell_color_blocked: default_cell_color_blocked(),
         }
     }
 
+    pub fn with_initial_layout(mut self, initial_layout: layout::InitialLayout) -> Self {
+        self.initial_layout = initial_layout;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_blocked_cells(mut self, blocked_cells: Vec<String>) -> Self {
+        self.blocked_cells = blocked_cells;
+        self
+    }
+
     pub fn board_size_x(&self) -> BoardSize {
         self.board_size_x
     }
@@ -81,6 +159,18 @@ impl BoardSettings {
         self.cell_color_clickable
     }
 
+    pub fn cell_color_hint(&self) -> Color {
+        self.cell_color_hint
+    }
+
+    pub fn cell_color_preview(&self) -> Color {
+        self.cell_color_preview
+    }
+
+    pub fn cell_color_blocked(&self) -> Color {
+        self.cell_color_blocked
+    }
+
     pub fn cell_color_background(&self) -> Color {
         self.cell_color_background
     }
@@ -104,11 +194,45 @@ impl BoardSettings {
     pub fn board_player_color_change_duration(&self) -> Duration {
         self.board_player_color_change_duration
     }
-}
 
-#[derive(Debug)]
-pub struct CellData {
-    pub position: board::BoardPosition,
+    pub fn flip_animation_duration(&self) -> Duration {
+        self.flip_animation_duration
+    }
+
+    pub fn flip_animation_stagger(&self) -> Duration {
+        self.flip_animation_stagger
+    }
+
+    pub fn initial_layout(&self) -> &layout::InitialLayout {
+        &self.initial_layout
+    }
+
+    pub fn blocked_cells(&self) -> &[String] {
+        &self.blocked_cells
+    }
+
+    pub fn size(&self) -> board::Size {
+        board::Size::new(self.board_size_x.size(), self.board_size_y.size()).unwrap()
+    }
+
+    // The board without discs, the blocked cells are missing from it.
+    pub fn empty_board(&self) -> Result<Board, layout::LayoutError> {
+        let mut board = Board::new(self.size());
+        for text in self.blocked_cells.iter() {
+            let position = notation::parse(text)
+                .map_err(|e| layout::LayoutError::Notation(text.clone(), e))?;
+            if !board.contains(&position) {
+                return Err(layout::LayoutError::OutOfBoard(position));
+            }
+            board.block(&position);
+        }
+        Ok(board)
+    }
+
+    // A new game on this board from the initial layout.
+    pub fn new_game(&self, first_turn: Turn) -> Result<GameData, layout::LayoutError> {
+        GameData::with_layout(first_turn, self.empty_board()?, self.initial_layout.clone())
+    }
 }
 
 #[derive(
@@ -157,38 +281,143 @@ impl From<Turn> for Player {
     }
 }
 
-#[derive(Debug, Clone)]
+// One entry of the game transcript.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Move {
+    Place(board::BoardPosition),
+    Pass,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryCommand {
+    Undo,
+    Redo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TurnStuckInfo {
     turn: Turn,
     turn_count: u16,
 }
 
-#[derive(Debug, Clone)]
+// Why a history couldn't be played again from the start.
+#[derive(Debug)]
+enum ReplayError {
+    Layout(layout::LayoutError),
+    IllegalMove,
+}
+
+impl From<layout::LayoutError> for ReplayError {
+    fn from(value: layout::LayoutError) -> Self {
+        ReplayError::Layout(value)
+    }
+}
+
+impl From<rules::MoveError> for ReplayError {
+    fn from(_: rules::MoveError) -> Self {
+        ReplayError::IllegalMove
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameData {
     first_turn: Turn,
     turn: Turn,
     turn_count: u16,
+    #[serde(with = "save::board_rows")]
     board: Board,
     turn_stuck_info_list: Vec<TurnStuckInfo>,
+    #[serde(with = "save::transcript")]
+    history: Vec<Move>,
+    #[serde(skip)]
+    redo_list: Vec<Move>, // undone moves, the next one to redo is last
+    #[serde(default)]
+    layout: layout::InitialLayout, // the game starts over from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    handicap: Option<layout::Handicap>, // placed on top of the layout
+    #[serde(default)]
+    rule_set: rules::RuleSet,
 }
 
 impl GameData {
     pub fn new(first_turn: Turn, board_size_x: BoardSize, board_size_y: BoardSize) -> Self {
         let size = board::Size::new(board_size_x.size(), board_size_y.size()).unwrap();
-        let board = Board::new(size);
-        GameData {
+        Self::with_layout(
+            first_turn,
+            Board::new(size),
+            layout::InitialLayout::default(),
+        )
+        .expect("the standard layout fits every board size")
+    }
+
+    // The layout is placed on the empty board, which may have blocked cells.
+    pub fn with_layout(
+        first_turn: Turn,
+        board: Board,
+        layout: layout::InitialLayout,
+    ) -> Result<Self, layout::LayoutError> {
+        let mut game_data = GameData {
             first_turn,
             turn: first_turn,
             turn_count: 0,
             board,
             turn_stuck_info_list: vec![],
+            history: vec![],
+            redo_list: vec![],
+            layout,
+            handicap: None,
+            rule_set: rules::RuleSet::default(),
+        };
+        game_data.set_initial_cells()?;
+        Ok(game_data)
+    }
+
+    // A new game where the weaker side starts with corners and the stronger side moves first.
+    pub fn with_handicap(
+        mut self,
+        handicap: layout::Handicap,
+    ) -> Result<Self, layout::LayoutError> {
+        self.handicap = Some(handicap);
+        self.first_turn = handicap.first_turn();
+        self.reset_checked()?;
+        Ok(self)
+    }
+
+    pub fn handicap(&self) -> Option<&layout::Handicap> {
+        self.handicap.as_ref()
+    }
+
+    pub fn layout(&self) -> &layout::InitialLayout {
+        &self.layout
+    }
+
+    pub fn with_rule_set(mut self, rule_set: rules::RuleSet) -> Self {
+        self.rule_set = rule_set;
+        self
+    }
+
+    pub fn rule_set(&self) -> rules::RuleSet {
+        self.rule_set
+    }
+
+    // The layout and the handicap discs on the cleared board.
+    fn set_initial_cells(&mut self) -> Result<(), layout::LayoutError> {
+        self.board.clear();
+        layout::set_initial_cells(&self.layout, &mut self.board)?;
+        if let Some(handicap) = &self.handicap {
+            layout::place_handicap(handicap, &mut self.board)?;
         }
+        Ok(())
     }
 
     pub fn turn(&self) -> &Turn {
         &self.turn
     }
 
+    pub fn turn_count(&self) -> u16 {
+        self.turn_count
+    }
+
     pub fn current_player(&self) -> Player {
         self.turn.into()
     }
@@ -230,10 +459,163 @@ impl GameData {
         }
     }
 
+    pub fn legal_moves(&self) -> Vec<board::BoardPosition> {
+        rules::legal_moves(&self.board, self.current_player())
+    }
+
+    // Play the current player's move and hand the turn over, returns the flipped positions.
+    pub fn play(&mut self, position: &board::BoardPosition) -> rules::MoveResult {
+        let flipped = self.place(position)?;
+        self.redo_list.clear();
+        Ok(flipped)
+    }
+
+    pub fn pass(&mut self) -> Result<(), rules::MoveError> {
+        self.skip()?;
+        self.redo_list.clear();
+        Ok(())
+    }
+
+    fn place(&mut self, position: &board::BoardPosition) -> rules::MoveResult {
+        let player = self.current_player();
+        let flipped = rules::apply_move(&mut self.board, position, player)?;
+        self.history.push(Move::Place(*position));
+        self.next_turn();
+        Ok(flipped)
+    }
+
+    fn skip(&mut self) -> Result<(), rules::MoveError> {
+        if rules::has_legal_move(&self.board, self.current_player()) {
+            return Err(rules::MoveError::HasLegalMove);
+        }
+        // once neither side can move the game is over, that isn't a pass
+        if !self.is_game_over() {
+            self.history.push(Move::Pass);
+        }
+        self.notify_turn_stuck();
+        self.next_turn();
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.iter().any(|m| matches!(m, Move::Place(_)))
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_list.is_empty()
+    }
+
+    // Take back the last placed disc together with the passes after it,
+    // the game is replayed from the start up to the move before.
+    pub fn undo(&mut self) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+        let mut moves = self.history.clone();
+        while let Some(m) = moves.pop() {
+            self.redo_list.push(m);
+            if let Move::Place(_) = m {
+                break;
+            }
+        }
+
+        self.replay(&moves)
+            .expect("moves from the history are legal");
+        true
+    }
+
+    // Play the last undone disc again together with the passes after it.
+    pub fn redo(&mut self) -> bool {
+        let Some(Move::Place(position)) = self.redo_list.last().copied() else {
+            return false;
+        };
+        if self.place(&position).is_err() {
+            return false;
+        }
+        self.redo_list.pop();
+        while let Some(Move::Pass) = self.redo_list.last() {
+            self.redo_list.pop();
+            self.skip().expect("undone passes are legal");
+        }
+        true
+    }
+
+    // Start over and play the moves again, the redo list is kept.
+    fn replay(&mut self, moves: &[Move]) -> Result<(), ReplayError> {
+        let redo_list = std::mem::take(&mut self.redo_list);
+        self.reset_checked()?;
+        self.redo_list = redo_list;
+        for m in moves {
+            match m {
+                Move::Place(position) => self.place(position).map(|_| ()),
+                Move::Pass => self.skip(),
+            }?;
+        }
+        Ok(())
+    }
+
+    // The game as it was after the first count moves of the history.
+    pub fn after_moves(&self, count: usize) -> GameData {
+        let mut game_data = self.clone();
+        game_data
+            .replay(&self.history[..count.min(self.history.len())])
+            .expect("moves from the history are legal");
+        game_data.redo_list.clear();
+        game_data
+    }
+
+    // Whether replaying the history from the start gives this game, a loaded game may not.
+    // The layout of a loaded game may not fit its board either.
+    pub fn is_consistent(&self) -> Result<bool, layout::LayoutError> {
+        let mut replayed = self.clone();
+        match replayed.replay(&self.history) {
+            Ok(()) => Ok(replayed.board == self.board
+                && replayed.turn == self.turn
+                && replayed.turn_count == self.turn_count),
+            Err(ReplayError::IllegalMove) => Ok(false),
+            Err(ReplayError::Layout(e)) => Err(e),
+        }
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        rules::is_game_over(&self.board)
+    }
+
+    pub fn score(&self) -> rules::Score {
+        rules::score(&self.board)
+    }
+
+    // Player::None means a draw, the rule set decides whether more or fewer discs win.
+    pub fn winner(&self) -> Player {
+        self.rule_set.winner(&self.score())
+    }
+
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    // The moves so far in standard notation as other tools read them, e.g. "f5d6c3d3c4",
+    // after a header for a start other than the standard one.
+    pub fn transcript(&self) -> String {
+        replay::to_string(self)
+    }
+
+    pub fn search_board(&self) -> rules::SearchBoard {
+        (&self.board).into()
+    }
+
     pub fn reset(&mut self) {
+        self.reset_checked()
+            .expect("the layout fitted the board when the game was created");
+    }
+
+    fn reset_checked(&mut self) -> Result<(), layout::LayoutError> {
         self.turn = self.first_turn;
         self.turn_count = 0;
-        self.board = Board::new(self.board.size);
+        self.set_initial_cells()?;
         self.turn_stuck_info_list = vec![];
+        self.history = vec![];
+        self.redo_list = vec![];
+        Ok(())
     }
 }
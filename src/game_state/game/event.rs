@@ -14,8 +14,19 @@ pub struct PlayerCellChanged {
     pub board_position: board::BoardPosition,
 }
 
+// A placed disc and the discs it flipped, to animate them.
+#[derive(Event)]
+pub struct DiscsFlipped {
+    pub player: data::Player,
+    pub placed: board::BoardPosition,
+    pub flipped: Vec<board::BoardPosition>,
+}
+
 #[derive(Event, Default)]
 pub struct TurnStuck;
 
 #[derive(Event, Default)]
 pub struct AfterInit;
+
+#[derive(Event, Deref)]
+pub struct HistoryRequest(pub data::HistoryCommand);
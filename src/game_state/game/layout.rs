@@ -0,0 +1,450 @@
+// The discs on the board before the first move, set in the board settings.
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use data::{Board, Player, Turn};
+
+pub const MAX_HANDICAP_CORNERS: u8 = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Opening {
+    #[default]
+    Diagonal, // the same colors on a diagonal, d4 and e5 are black on 8x8
+    Cross, // the same colors side by side, d4 and d5 are black on 8x8
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InitialLayout {
+    // the 2x2 block in the middle, turned clockwise by quarter turns
+    Standard {
+        #[serde(default)]
+        opening: Opening,
+        #[serde(default)]
+        quarter_turns: u8,
+    },
+    // discs at positions in standard notation like "d4", the other cells are empty
+    Cells(Vec<(String, Player)>),
+    // one string per row from row 1, 'X' for black, 'O' for white and '-' or '.' for empty
+    Diagram(Vec<String>),
+}
+
+impl Default for InitialLayout {
+    fn default() -> Self {
+        InitialLayout::Standard {
+            opening: Opening::Diagonal,
+            quarter_turns: 0,
+        }
+    }
+}
+
+// Corner discs for the weaker side on top of the initial layout, the stronger side moves first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handicap {
+    #[serde(default = "default_handicap_player")]
+    pub player: Player, // the weaker side
+    pub corners: u8, // 1 to 4, taken in the order a1, h8, h1, a8 on 8x8
+}
+
+impl std::fmt::Display for Handicap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} starts with {} corner(s), {:?} moves first",
+            self.player,
+            self.corners,
+            self.first_turn()
+        )
+    }
+}
+
+fn default_handicap_player() -> Player {
+    Player::Black
+}
+
+impl Handicap {
+    pub fn first_turn(&self) -> Turn {
+        match self.player {
+            Player::White => Turn::Black,
+            _ => Turn::White,
+        }
+    }
+
+    // Opposite corners first, so two corners don't share a side.
+    pub fn corner_cells(&self, size: board::Size) -> Vec<board::BoardPosition> {
+        let (last_x, last_y) = (size.x() as i64 - 1, size.y() as i64 - 1);
+        [(0, 0), (last_x, last_y), (last_x, 0), (0, last_y)]
+            .into_iter()
+            .take(self.corners.into())
+            .map(Into::into)
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    Notation(String, notation::NotationError),
+    OutOfBoard(board::BoardPosition),
+    Blocked(board::BoardPosition),
+    Duplicate(board::BoardPosition),
+    Occupied(board::BoardPosition),
+    HandicapCorners(u8),
+    NoPlayer(board::BoardPosition),
+    RowCount {
+        expected: u16,
+        found: usize,
+    },
+    RowLength {
+        row: usize,
+        expected: u16,
+        found: usize,
+    },
+    InvalidCell {
+        row: usize,
+        cell: char,
+    },
+}
+
+impl std::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayoutError::Notation(text, e) => write!(f, "invalid position \"{}\": {:?}", text, e),
+            LayoutError::OutOfBoard(position) => {
+                write!(f, "{} is outside the board", notation::to_string(position))
+            }
+            LayoutError::Blocked(position) => {
+                write!(f, "{} is a blocked cell", notation::to_string(position))
+            }
+            LayoutError::Duplicate(position) => {
+                write!(f, "{} is set more than once", notation::to_string(position))
+            }
+            LayoutError::Occupied(position) => write!(
+                f,
+                "the handicap disc on {} covers a disc of the initial layout",
+                notation::to_string(position)
+            ),
+            LayoutError::HandicapCorners(corners) => write!(
+                f,
+                "a handicap of {} corners is unsupported, it takes 1 to {}",
+                corners, MAX_HANDICAP_CORNERS
+            ),
+            LayoutError::NoPlayer(position) => {
+                write!(f, "{} needs Black or White", notation::to_string(position))
+            }
+            LayoutError::RowCount { expected, found } => {
+                write!(f, "the diagram has {} rows, the board {}", found, expected)
+            }
+            LayoutError::RowLength {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} of the diagram has {} cells, the board {}",
+                row + 1,
+                found,
+                expected
+            ),
+            LayoutError::InvalidCell { row, cell } => {
+                write!(
+                    f,
+                    "invalid cell '{}' in row {} of the diagram",
+                    cell,
+                    row + 1
+                )
+            }
+        }
+    }
+}
+
+// The block offset (dx, dy) after a clockwise quarter turn inside the 2x2 block.
+fn turn_clockwise(
+    (dx, dy): (board::PositionUnit, board::PositionUnit),
+) -> (board::PositionUnit, board::PositionUnit) {
+    (1 - dy, dx)
+}
+
+fn standard_cells(
+    size: board::Size,
+    opening: Opening,
+    quarter_turns: u8,
+) -> Vec<(board::BoardPosition, Player)> {
+    let corner = rules::initial_cells(size)[0].0;
+    let block = match opening {
+        Opening::Diagonal => [
+            ((0, 0), Player::Black),
+            ((1, 0), Player::White),
+            ((0, 1), Player::White),
+            ((1, 1), Player::Black),
+        ],
+        Opening::Cross => [
+            ((0, 0), Player::Black),
+            ((1, 0), Player::White),
+            ((0, 1), Player::Black),
+            ((1, 1), Player::White),
+        ],
+    };
+    block
+        .into_iter()
+        .map(|(mut offset, player)| {
+            for _ in 0..quarter_turns % 4 {
+                offset = turn_clockwise(offset);
+            }
+            ((corner.x + offset.0, corner.y + offset.1).into(), player)
+        })
+        .collect()
+}
+
+fn diagram_cells(
+    size: board::Size,
+    rows: &[String],
+) -> Result<Vec<(board::BoardPosition, Player)>, LayoutError> {
+    if rows.len() != size.y() as usize {
+        return Err(LayoutError::RowCount {
+            expected: size.y(),
+            found: rows.len(),
+        });
+    }
+    let mut cells = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        let row_cells: Vec<char> = row.chars().filter(|c| !c.is_whitespace()).collect();
+        if row_cells.len() != size.x() as usize {
+            return Err(LayoutError::RowLength {
+                row: y,
+                expected: size.x(),
+                found: row_cells.len(),
+            });
+        }
+        for (x, cell) in row_cells.into_iter().enumerate() {
+            let player = match cell {
+                'X' | 'x' => Player::Black,
+                'O' | 'o' => Player::White,
+                '-' | '.' => continue,
+                cell => return Err(LayoutError::InvalidCell { row: y, cell }),
+            };
+            cells.push(((x as i64, y as i64).into(), player));
+        }
+    }
+    Ok(cells)
+}
+
+// The discs of the layout on the empty board, or why the layout doesn't fit it.
+pub fn initial_cells(
+    layout: &InitialLayout,
+    board: &Board,
+) -> Result<Vec<(board::BoardPosition, Player)>, LayoutError> {
+    let size = board.size;
+    let cells = match layout {
+        InitialLayout::Standard {
+            opening,
+            quarter_turns,
+        } => standard_cells(size, *opening, *quarter_turns),
+        InitialLayout::Cells(cells) => cells
+            .iter()
+            .map(|(text, player)| {
+                notation::parse(text)
+                    .map(|position| (position, *player))
+                    .map_err(|e| LayoutError::Notation(text.clone(), e))
+            })
+            .collect::<Result<_, _>>()?,
+        InitialLayout::Diagram(rows) => diagram_cells(size, rows)?,
+    };
+
+    let mut seen = vec![];
+    for (position, player) in cells.iter() {
+        if board.is_blocked(position) {
+            return Err(LayoutError::Blocked(*position));
+        }
+        if board.cell_ref(position).is_none() {
+            return Err(LayoutError::OutOfBoard(*position));
+        }
+        if *player == Player::None {
+            return Err(LayoutError::NoPlayer(*position));
+        }
+        if seen.contains(position) {
+            return Err(LayoutError::Duplicate(*position));
+        }
+        seen.push(*position);
+    }
+    Ok(cells)
+}
+
+pub fn set_initial_cells(layout: &InitialLayout, board: &mut Board) -> Result<(), LayoutError> {
+    for (position, player) in initial_cells(layout, board)? {
+        if let Some(cell) = board.cell_mut(&position) {
+            *cell = player;
+        }
+    }
+    Ok(())
+}
+
+pub fn place_handicap(handicap: &Handicap, board: &mut Board) -> Result<(), LayoutError> {
+    if !(1..=MAX_HANDICAP_CORNERS).contains(&handicap.corners) {
+        return Err(LayoutError::HandicapCorners(handicap.corners));
+    }
+    for position in handicap.corner_cells(board.size) {
+        match board.cell_mut(&position) {
+            None => return Err(LayoutError::Blocked(position)),
+            Some(Player::Black | Player::White) => return Err(LayoutError::Occupied(position)),
+            Some(_) if handicap.player == Player::None => {
+                return Err(LayoutError::NoPlayer(position))
+            }
+            Some(cell) => *cell = handicap.player,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn size() -> board::Size {
+        board::Size::new(8, 8).unwrap()
+    }
+
+    fn cells_of(layout: &InitialLayout, player: Player) -> String {
+        let mut positions: Vec<_> = initial_cells(layout, &Board::new(size()))
+            .unwrap()
+            .into_iter()
+            .filter(|(_, cell_player)| *cell_player == player)
+            .map(|(position, _)| position)
+            .collect();
+        positions.sort_by_key(|position| (position.y, position.x));
+        notation::sequence_to_string(&positions)
+    }
+
+    #[test]
+    fn standard_layouts() {
+        let mut board = Board::new(size());
+        rules::set_initial_cells(&mut board);
+        let mut default_board = Board::new(size());
+        set_initial_cells(&InitialLayout::default(), &mut default_board).unwrap();
+        assert_eq!(board, default_board);
+
+        let standard = |opening, quarter_turns| InitialLayout::Standard {
+            opening,
+            quarter_turns,
+        };
+        assert_eq!(
+            cells_of(&standard(Opening::Diagonal, 1), Player::Black),
+            "e4d5"
+        );
+        assert_eq!(
+            cells_of(&standard(Opening::Diagonal, 2), Player::Black),
+            "d4e5"
+        );
+        assert_eq!(
+            cells_of(&standard(Opening::Cross, 0), Player::Black),
+            "d4d5"
+        );
+        assert_eq!(
+            cells_of(&standard(Opening::Cross, 1), Player::Black),
+            "d4e4"
+        );
+        assert_eq!(
+            cells_of(&standard(Opening::Cross, 5), Player::White),
+            "d5e5"
+        );
+    }
+
+    #[test]
+    fn explicit_layouts() {
+        let cells = InitialLayout::Cells(vec![
+            ("a1".to_string(), Player::Black),
+            ("h8".to_string(), Player::White),
+        ]);
+        assert_eq!(cells_of(&cells, Player::Black), "a1");
+
+        let mut rows = vec!["--------".to_string(); 8];
+        rows[0] = "X- - - - - - O".to_string();
+        rows[7] = "......ox".to_string();
+        let diagram = InitialLayout::Diagram(rows.clone());
+        assert_eq!(cells_of(&diagram, Player::Black), "a1h8");
+        assert_eq!(cells_of(&diagram, Player::White), "h1g8");
+
+        let mut board = Board::new(size());
+        board.block(&(1, 1).into());
+        let check = |layout| initial_cells(&layout, &board).unwrap_err();
+        assert_eq!(
+            check(InitialLayout::Cells(vec![(
+                "b2".to_string(),
+                Player::Black
+            )])),
+            LayoutError::Blocked((1, 1).into())
+        );
+        assert_eq!(
+            check(InitialLayout::Cells(vec![(
+                "i1".to_string(),
+                Player::Black
+            )])),
+            LayoutError::OutOfBoard((8, 0).into())
+        );
+        assert_eq!(
+            check(InitialLayout::Cells(vec![
+                ("a1".to_string(), Player::Black),
+                ("a1".to_string(), Player::White),
+            ])),
+            LayoutError::Duplicate((0, 0).into())
+        );
+        assert_eq!(
+            check(InitialLayout::Cells(vec![("a1".to_string(), Player::None)])),
+            LayoutError::NoPlayer((0, 0).into())
+        );
+        assert!(matches!(
+            check(InitialLayout::Cells(vec![(
+                "1a".to_string(),
+                Player::Black
+            )])),
+            LayoutError::Notation(..)
+        ));
+        assert_eq!(
+            check(InitialLayout::Diagram(rows[..7].to_vec())),
+            LayoutError::RowCount {
+                expected: 8,
+                found: 7
+            }
+        );
+        rows[3] = "---".to_string();
+        assert_eq!(
+            check(InitialLayout::Diagram(rows.clone())),
+            LayoutError::RowLength {
+                row: 3,
+                expected: 8,
+                found: 3
+            }
+        );
+        rows[3] = "---?----".to_string();
+        assert_eq!(
+            check(InitialLayout::Diagram(rows)),
+            LayoutError::InvalidCell { row: 3, cell: '?' }
+        );
+    }
+
+    #[test]
+    fn handicap_corners() {
+        let handicap = |player, corners| Handicap { player, corners };
+        let place = |handicap: Handicap| {
+            let mut board = Board::new(size());
+            set_initial_cells(&InitialLayout::default(), &mut board).unwrap();
+            place_handicap(&handicap, &mut board).map(|_| board)
+        };
+        let board = place(handicap(Player::White, 4)).unwrap();
+        for corner in ["a1", "h8", "h1", "a8"] {
+            let position = notation::parse(corner).unwrap();
+            assert_eq!(board.cell_ref(&position), Some(&Player::White));
+        }
+        assert_eq!(handicap(Player::White, 4).first_turn(), Turn::Black);
+        assert_eq!(handicap(Player::Black, 1).first_turn(), Turn::White);
+
+        for corners in [0, 5] {
+            assert_eq!(
+                place(handicap(Player::Black, corners)),
+                Err(LayoutError::HandicapCorners(corners))
+            );
+        }
+        assert!(matches!(
+            place(handicap(Player::None, 1)),
+            Err(LayoutError::NoPlayer(_))
+        ));
+    }
+}
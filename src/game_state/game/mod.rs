@@ -1,9 +1,18 @@
+pub mod ai;
+pub mod bitboard;
 #[allow(dead_code)]
 mod component;
 pub mod data;
 mod event;
+pub mod layout;
+pub mod network;
+pub mod notation;
 pub mod plugin;
+pub mod replay;
 mod resource;
+pub mod rules;
+pub mod save;
+pub mod spectator;
 mod system;
 
 use super::{board, position_pairs, result, util, GameState};
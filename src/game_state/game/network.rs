@@ -0,0 +1,403 @@
+// Two players on a local network: the host listens on a TCP port and the client joins it.
+// Messages are JSON lines. Each side plays its own clicks and sends them as event::CellClick
+// positions, the host checks the client's moves before playing them.
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::*;
+use data::{GameData, Turn};
+
+pub const HOST_TURN: Turn = Turn::Black;
+
+const READ_CHUNK_SIZE: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Message {
+    // sent by the host once connected, turn is the one the client plays
+    Hello {
+        turn: Turn,
+        first_turn: Turn,
+        board_size_x: u16,
+        board_size_y: u16,
+        initial_layout: layout::InitialLayout,
+        blocked_cells: Vec<String>,
+        handicap: Option<layout::Handicap>,
+        #[serde(default)]
+        rule_set: rules::RuleSet,
+    },
+    CellClick(board::BoardPosition),
+    // moves is the length of the host's history, the client goes back to it
+    Rejected {
+        reason: String,
+        moves: usize,
+    },
+}
+
+#[derive(Debug)]
+pub enum NetworkError {
+    IO(std::io::Error),
+    Read(serde_json::Error),
+    Disconnected,
+    UnexpectedMessage(Message),
+    SettingsMismatch,
+    OutOfTurn(Turn),
+    IllegalMove(rules::MoveError),
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::IO(e) => write!(f, "connection error: {}", e),
+            NetworkError::Read(e) => write!(f, "cannot read message: {}", e),
+            NetworkError::Disconnected => write!(f, "the other player disconnected"),
+            NetworkError::UnexpectedMessage(message) => {
+                write!(f, "unexpected message {:?}", message)
+            }
+            NetworkError::SettingsMismatch => {
+                write!(
+                    f,
+                    "the board settings, handicap, rule set or first turn differ from the host's"
+                )
+            }
+            NetworkError::OutOfTurn(turn) => write!(f, "it's {:?}'s turn", turn),
+            NetworkError::IllegalMove(e) => write!(f, "illegal move {:?}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for NetworkError {
+    fn from(value: std::io::Error) -> Self {
+        NetworkError::IO(value)
+    }
+}
+
+impl From<serde_json::Error> for NetworkError {
+    fn from(value: serde_json::Error) -> Self {
+        NetworkError::Read(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Host,
+    Client,
+}
+
+// Messages as JSON lines on a TCP stream.
+pub(super) struct LineStream {
+    stream: TcpStream,
+    buffer: Vec<u8>, // received bytes not yet making a whole line
+}
+
+impl LineStream {
+    pub fn new(stream: TcpStream) -> Self {
+        LineStream {
+            stream,
+            buffer: vec![],
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), NetworkError> {
+        Ok(self.stream.set_nonblocking(nonblocking)?)
+    }
+
+    pub fn send<T: Serialize>(&mut self, message: &T) -> Result<(), NetworkError> {
+        self.send_line(&to_line(message)?)
+    }
+
+    pub fn send_line(&mut self, line: &[u8]) -> Result<(), NetworkError> {
+        self.stream.write_all(line)?;
+        Ok(())
+    }
+
+    // The next whole message, None when nothing has arrived yet.
+    pub fn receive<T: DeserializeOwned>(&mut self) -> Result<Option<T>, NetworkError> {
+        loop {
+            if let Some(index) = self.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=index).collect();
+                return Ok(Some(serde_json::from_slice(&line)?));
+            }
+            let mut chunk = [0; READ_CHUNK_SIZE];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(NetworkError::Disconnected),
+                Ok(length) => self.buffer.extend_from_slice(&chunk[..length]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+pub(super) fn to_line<T: Serialize>(message: &T) -> Result<Vec<u8>, NetworkError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+pub struct Peer {
+    role: Role,
+    local_turn: Turn,
+    stream: LineStream,
+}
+
+fn hello(
+    board_settings: &data::BoardSettings,
+    first_turn: Turn,
+    handicap: Option<layout::Handicap>,
+    rule_set: rules::RuleSet,
+) -> Message {
+    Message::Hello {
+        turn: HOST_TURN.next(),
+        first_turn,
+        board_size_x: board_settings.board_size_x().size(),
+        board_size_y: board_settings.board_size_y().size(),
+        initial_layout: board_settings.initial_layout().clone(),
+        blocked_cells: board_settings.blocked_cells().to_vec(),
+        handicap,
+        rule_set,
+    }
+}
+
+impl Peer {
+    // Blocks until a client joins.
+    pub fn host(
+        port: u16,
+        board_settings: &data::BoardSettings,
+        first_turn: Turn,
+        handicap: Option<layout::Handicap>,
+        rule_set: rules::RuleSet,
+    ) -> Result<Self, NetworkError> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        bevy::prelude::info!("waiting for a player on port {}", port);
+        Self::accept(&listener, board_settings, first_turn, handicap, rule_set)
+    }
+
+    pub fn accept(
+        listener: &TcpListener,
+        board_settings: &data::BoardSettings,
+        first_turn: Turn,
+        handicap: Option<layout::Handicap>,
+        rule_set: rules::RuleSet,
+    ) -> Result<Self, NetworkError> {
+        let (stream, address) = listener.accept()?;
+        bevy::prelude::info!("player joined from {}", address);
+        let mut peer = Peer {
+            role: Role::Host,
+            local_turn: HOST_TURN,
+            stream: LineStream::new(stream),
+        };
+        peer.send(&hello(board_settings, first_turn, handicap, rule_set))?;
+        peer.stream.set_nonblocking(true)?;
+        Ok(peer)
+    }
+
+    // Blocks until the host says which turn this side plays.
+    pub fn join(
+        address: impl ToSocketAddrs,
+        board_settings: &data::BoardSettings,
+        first_turn: Turn,
+        handicap: Option<layout::Handicap>,
+        rule_set: rules::RuleSet,
+    ) -> Result<Self, NetworkError> {
+        let stream = TcpStream::connect(address)?;
+        let mut peer = Peer {
+            role: Role::Client,
+            local_turn: HOST_TURN.next(),
+            stream: LineStream::new(stream),
+        };
+        match peer.receive()? {
+            Some(Message::Hello {
+                turn,
+                first_turn: host_first_turn,
+                board_size_x,
+                board_size_y,
+                initial_layout,
+                blocked_cells,
+                handicap: host_handicap,
+                rule_set: host_rule_set,
+            }) => {
+                if host_first_turn != first_turn
+                    || board_size_x != board_settings.board_size_x().size()
+                    || board_size_y != board_settings.board_size_y().size()
+                    || initial_layout != *board_settings.initial_layout()
+                    || blocked_cells != board_settings.blocked_cells()
+                    || host_handicap != handicap
+                    || host_rule_set != rule_set
+                {
+                    return Err(NetworkError::SettingsMismatch);
+                }
+                peer.local_turn = turn;
+            }
+            Some(message) => return Err(NetworkError::UnexpectedMessage(message)),
+            None => return Err(NetworkError::Disconnected),
+        }
+        peer.stream.set_nonblocking(true)?;
+        Ok(peer)
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn local_turn(&self) -> Turn {
+        self.local_turn
+    }
+
+    pub fn is_local_turn(&self, turn: &Turn) -> bool {
+        self.local_turn == *turn
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), NetworkError> {
+        self.stream.send(message)
+    }
+
+    // The next whole message, None when nothing has arrived yet.
+    pub fn receive(&mut self) -> Result<Option<Message>, NetworkError> {
+        self.stream.receive()
+    }
+
+    // A move from the other side is played only on its turn and only when it's legal.
+    pub fn check_remote_move(
+        &self,
+        game_data: &GameData,
+        position: &board::BoardPosition,
+    ) -> Result<(), NetworkError> {
+        if self.is_local_turn(game_data.turn()) {
+            return Err(NetworkError::OutOfTurn(*game_data.turn()));
+        }
+        let mut board = game_data.board().clone();
+        rules::apply_move(&mut board, position, game_data.current_player())
+            .map_err(NetworkError::IllegalMove)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::Color;
+
+    use super::*;
+
+    fn board_settings() -> data::BoardSettings {
+        data::BoardSettings::new(
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+            Color::RED,
+            std::iter::empty(),
+            Color::GRAY,
+            std::iter::empty(),
+            std::time::Duration::ZERO,
+        )
+    }
+
+    fn connect() -> (Peer, Peer) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            Peer::join(
+                address,
+                &board_settings(),
+                Turn::Black,
+                None,
+                rules::RuleSet::Standard,
+            )
+        });
+        let host = Peer::accept(
+            &listener,
+            &board_settings(),
+            Turn::Black,
+            None,
+            rules::RuleSet::Standard,
+        )
+        .unwrap();
+        (host, client.join().unwrap().unwrap())
+    }
+
+    // the receiving side isn't blocking, wait for the message to arrive
+    fn wait_for_message(peer: &mut Peer) -> Message {
+        loop {
+            if let Some(message) = peer.receive().unwrap() {
+                return message;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn host_checks_client_moves() {
+        let (mut host, mut client) = connect();
+        assert_eq!(host.local_turn(), Turn::Black);
+        assert_eq!(client.local_turn(), Turn::White);
+        assert_eq!(host.receive().unwrap(), None);
+
+        let mut game_data =
+            GameData::new(Turn::Black, 8.try_into().unwrap(), 8.try_into().unwrap());
+        let e6 = notation::parse("e6").unwrap();
+        client.send(&Message::CellClick(e6)).unwrap();
+        assert_eq!(wait_for_message(&mut host), Message::CellClick(e6));
+        assert!(matches!(
+            host.check_remote_move(&game_data, &e6),
+            Err(NetworkError::OutOfTurn(Turn::Black))
+        ));
+
+        let c5 = notation::parse("c5").unwrap();
+        game_data.play(&c5).unwrap();
+        host.send(&Message::CellClick(c5)).unwrap();
+        assert_eq!(wait_for_message(&mut client), Message::CellClick(c5));
+        assert!(client.check_remote_move(&game_data, &c5).is_err());
+        assert!(host.check_remote_move(&game_data, &e6).is_ok());
+        assert!(matches!(
+            host.check_remote_move(&game_data, &notation::parse("a1").unwrap()),
+            Err(NetworkError::IllegalMove(_))
+        ));
+
+        // a malformed line is dropped, the connection goes on
+        client.stream.send_line(b"not json\n").unwrap();
+        client.send(&Message::CellClick(e6)).unwrap();
+        loop {
+            match host.receive() {
+                Err(NetworkError::Read(_)) => break,
+                Ok(None) => std::thread::sleep(std::time::Duration::from_millis(1)),
+                other => panic!("{:?}", other.map_err(|e| e.to_string())),
+            }
+        }
+        assert_eq!(wait_for_message(&mut host), Message::CellClick(e6));
+
+        drop(client);
+        loop {
+            match host.receive() {
+                Err(NetworkError::Disconnected) => break,
+                Ok(None) => std::thread::sleep(std::time::Duration::from_millis(1)),
+                other => panic!("{:?}", other.map_err(|e| e.to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn join_needs_the_same_settings() {
+        for (first_turn, rule_set) in [
+            (Turn::White, rules::RuleSet::Standard),
+            (Turn::Black, rules::RuleSet::Anti),
+        ] {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap();
+            let client = std::thread::spawn(move || {
+                Peer::join(address, &board_settings(), first_turn, None, rule_set)
+            });
+            let _host = Peer::accept(
+                &listener,
+                &board_settings(),
+                Turn::Black,
+                None,
+                rules::RuleSet::Standard,
+            )
+            .unwrap();
+            assert!(matches!(
+                client.join().unwrap(),
+                Err(NetworkError::SettingsMismatch)
+            ));
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// Standard move notation: a column letter from 'a' and a row number from 1, "a1" is (0, 0).
+// A pass is written "pa".
+use super::*;
+use data::Move;
+
+const FIRST_COLUMN: char = 'a';
+pub const PASS: &str = "pa";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NotationError {
+    Empty,
+    InvalidColumn(char),
+    InvalidRow(String),
+    UnexpectedPass,
+}
+
+pub fn column_char(x: board::PositionUnit) -> char {
+    (FIRST_COLUMN as u8 + x as u8) as char
+}
+
+pub fn to_string(position: &board::BoardPosition) -> String {
+    format!("{}{}", column_char(position.x), position.y + 1)
+}
+
+#[allow(dead_code)]
+pub fn sequence_to_string(positions: &[board::BoardPosition]) -> String {
+    positions.iter().map(to_string).collect()
+}
+
+pub fn parse(text: &str) -> Result<board::BoardPosition, NotationError> {
+    match parse_sequence(text)?.as_slice() {
+        [position] => Ok(*position),
+        [] => Err(NotationError::Empty),
+        _ => Err(NotationError::InvalidRow(text.to_string())),
+    }
+}
+
+pub fn move_to_string(m: &Move) -> String {
+    match m {
+        Move::Place(position) => to_string(position),
+        Move::Pass => PASS.to_string(),
+    }
+}
+
+// A whole game back to back like "f5d6c3d3c4", passes included.
+pub fn transcript_to_string(moves: &[Move]) -> String {
+    moves.iter().map(move_to_string).collect()
+}
+
+// Other tools write moves from the standard start with white on d4 and e5, the board here starts
+// with black there. Mirroring left to right maps the one start onto the other, both ways.
+pub fn standard_orientation(
+    position: &board::BoardPosition,
+    size: board::Size,
+) -> board::BoardPosition {
+    (size.x() as board::PositionUnit - 1 - position.x, position.y).into()
+}
+
+pub fn standard_move(m: &Move, size: board::Size) -> Move {
+    match m {
+        Move::Place(position) => Move::Place(standard_orientation(position, size)),
+        Move::Pass => Move::Pass,
+    }
+}
+
+// A transcript as other tools read it.
+pub fn standard_transcript_to_string(moves: &[Move], size: board::Size) -> String {
+    moves
+        .iter()
+        .map(|m| move_to_string(&standard_move(m, size)))
+        .collect()
+}
+
+// A transcript written by other tools, as moves on the board here.
+pub fn parse_standard_transcript(
+    text: &str,
+    size: board::Size,
+) -> Result<Vec<Move>, NotationError> {
+    Ok(parse_transcript(text)?
+        .iter()
+        .map(|m| standard_move(m, size))
+        .collect())
+}
+
+// Moves written back to back, rows can have more than one digit. Whitespace and case are ignored.
+pub fn parse_transcript(text: &str) -> Result<Vec<Move>, NotationError> {
+    let mut chars = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .peekable();
+    let mut moves = vec![];
+    while let Some(column) = chars.next() {
+        // a column is always followed by a row number, so "pa" can't be a position
+        if column == 'p' && chars.next_if_eq(&'a').is_some() {
+            moves.push(Move::Pass);
+            continue;
+        }
+        if !column.is_ascii_lowercase() {
+            return Err(NotationError::InvalidColumn(column));
+        }
+        let mut row = String::new();
+        while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+            row.push(digit);
+        }
+        let y = match row.parse::<board::PositionUnit>() {
+            Ok(row) if row > 0 => row - 1,
+            _ => return Err(NotationError::InvalidRow(row)),
+        };
+        let x = (column as u8 - FIRST_COLUMN as u8) as board::PositionUnit;
+        moves.push(Move::Place((x, y).into()));
+    }
+    Ok(moves)
+}
+
+// Same as parse_transcript, for lines which can't have a pass.
+pub fn parse_sequence(text: &str) -> Result<Vec<board::BoardPosition>, NotationError> {
+    parse_transcript(text)?
+        .into_iter()
+        .map(|m| match m {
+            Move::Place(position) => Ok(position),
+            Move::Pass => Err(NotationError::UnexpectedPass),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let positions = parse_sequence("f5d6C3a10").unwrap();
+        assert_eq!(
+            positions,
+            vec![(5, 4).into(), (3, 5).into(), (2, 2).into(), (0, 9).into()]
+        );
+        assert_eq!(sequence_to_string(&positions), "f5d6c3a10");
+        assert_eq!(parse("h8"), Ok((7, 7).into()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse(""), Err(NotationError::Empty));
+        assert_eq!(parse("5f"), Err(NotationError::InvalidColumn('5')));
+        assert_eq!(parse("f0"), Err(NotationError::InvalidRow("0".to_string())));
+        assert_eq!(parse("f"), Err(NotationError::InvalidRow("".to_string())));
+        assert_eq!(parse("pa"), Err(NotationError::UnexpectedPass));
+    }
+
+    #[test]
+    fn transcript_with_pass() {
+        let moves = parse_transcript("f5 d6 PA p3").unwrap();
+        assert_eq!(
+            moves,
+            vec![
+                Move::Place((5, 4).into()),
+                Move::Place((3, 5).into()),
+                Move::Pass,
+                Move::Place((15, 2).into()),
+            ]
+        );
+        assert_eq!(transcript_to_string(&moves), "f5d6pap3");
+    }
+
+    #[test]
+    fn standard_opening() {
+        let size = board::Size::new(8, 8).unwrap();
+        let mut game_data = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        let moves = parse_standard_transcript("f5", size).unwrap();
+        assert_eq!(moves, vec![Move::Place(parse("c5").unwrap())]);
+        let Move::Place(position) = moves[0] else {
+            unreachable!()
+        };
+        assert!(game_data.play(&position).is_ok());
+        assert_eq!(
+            standard_transcript_to_string(game_data.history(), size),
+            "f5"
+        );
+        assert_eq!(game_data.transcript(), "f5");
+    }
+}
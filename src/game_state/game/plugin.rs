@@ -1,5 +1,7 @@
+use std::sync::Mutex;
+
 use crate::game_state::util::*;
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 use serde::{Deserialize, Serialize};
 
 use super::*;
@@ -8,6 +10,20 @@ use super::*;
 pub struct GamePlugin {
     first_turn: data::Turn,
     board_settings: data::BoardSettings,
+    #[serde(default)]
+    player_controllers: HashMap<data::Turn, ai::PlayerController>, // missing turns are played by humans
+    #[serde(default)]
+    hint_settings: ai::HintSettings,
+    #[serde(default)]
+    spectator_port: Option<u16>, // broadcast the game to spectators on this port
+    #[serde(default)]
+    handicap: Option<layout::Handicap>, // overrides first_turn, the stronger side moves first
+    #[serde(default)]
+    rule_set: rules::RuleSet,
+    #[serde(skip)]
+    saved_game: Option<data::GameData>,
+    #[serde(skip)]
+    replay: Option<Vec<data::Move>>,
 }
 
 #[allow(dead_code)]
@@ -16,26 +32,117 @@ impl GamePlugin {
         GamePlugin {
             first_turn,
             board_settings,
+            player_controllers: HashMap::default(),
+            hint_settings: ai::HintSettings::default(),
+            spectator_port: None,
+            handicap: None,
+            rule_set: rules::RuleSet::default(),
+            saved_game: None,
+            replay: None,
+        }
+    }
+
+    pub fn with_player_controller(
+        mut self,
+        turn: data::Turn,
+        player_controller: ai::PlayerController,
+    ) -> Self {
+        self.player_controllers.insert(turn, player_controller);
+        self
+    }
+
+    pub fn with_handicap(mut self, handicap: layout::Handicap) -> Self {
+        self.handicap = Some(handicap);
+        self
+    }
+
+    pub fn with_rule_set(mut self, rule_set: rules::RuleSet) -> Self {
+        self.rule_set = rule_set;
+        self
+    }
+
+    pub fn rule_set(&self) -> rules::RuleSet {
+        self.rule_set
+    }
+
+    pub fn first_turn(&self) -> data::Turn {
+        match &self.handicap {
+            Some(handicap) => handicap.first_turn(),
+            None => self.first_turn,
+        }
+    }
+
+    pub fn handicap(&self) -> Option<&layout::Handicap> {
+        self.handicap.as_ref()
+    }
+
+    pub fn board_settings(&self) -> &data::BoardSettings {
+        &self.board_settings
+    }
+
+    pub fn player_controllers(&self) -> &HashMap<data::Turn, ai::PlayerController> {
+        &self.player_controllers
+    }
+
+    // Start with a saved game instead of a new one.
+    pub fn with_saved_game(mut self, game_data: data::GameData) -> Result<Self, save::SaveError> {
+        save::check_board(&game_data, &self.board_settings)?;
+        self.saved_game = Some(game_data);
+        Ok(self)
+    }
+
+    // Play back a recorded game instead of playing one, from the start in its header.
+    // Every move has to be legal.
+    pub fn with_replay(
+        mut self,
+        transcript: replay::Transcript,
+    ) -> Result<Self, replay::ReplayError> {
+        self.board_settings = self.board_settings.with_initial_layout(transcript.layout);
+        self.handicap = transcript.handicap;
+        replay::validate(&self.new_game()?, &transcript.moves)?;
+        self.replay = Some(transcript.moves);
+        Ok(self)
+    }
+
+    // A new game from the initial layout and the handicap, checked when the settings are loaded.
+    pub fn new_game(&self) -> Result<data::GameData, layout::LayoutError> {
+        let game_data = self
+            .board_settings
+            .new_game(self.first_turn)?
+            .with_rule_set(self.rule_set);
+        match self.handicap {
+            Some(handicap) => game_data.with_handicap(handicap),
+            None => Ok(game_data),
         }
     }
+
+    // The standard layout when the settings don't fit the board.
+    fn new_game_data(&self) -> data::GameData {
+        self.new_game().unwrap_or_else(|e| {
+            error!("Cannot set up the board: {}", e);
+            data::GameData::new(
+                self.first_turn,
+                self.board_settings.board_size_x(),
+                self.board_settings.board_size_y(),
+            )
+            .with_rule_set(self.rule_set)
+        })
+    }
 }
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.insert_resource(resource::BoardSettings(self.board_settings.clone()))
-            .insert_resource::<resource::GameData>(
-                data::GameData::new(
-                    self.first_turn,
-                    self.board_settings.board_size_x(),
-                    self.board_settings.board_size_y(),
-                )
-                .into(),
-            )
+            .insert_resource(resource::PlayerControllers(self.player_controllers.clone()))
+            .insert_resource(resource::HintSettings(self.hint_settings.clone()))
+            .insert_resource::<resource::GameData>(self.new_game_data().into())
             .add_event::<event::AfterInit>()
             .add_event::<event::PlayerCellChanged>()
             .add_event::<event::CellClick>()
             .add_event::<event::TurnChange>()
+            .add_event::<event::DiscsFlipped>()
             .add_event::<event::TurnStuck>()
+            .add_event::<event::HistoryRequest>()
             .add_systems(
                 OnEnter(GameState::Game),
                 (
@@ -50,6 +157,8 @@ impl Plugin for GamePlugin {
                 (
                     despawn_entities_and_clear_resource::<resource::Entities>,
                     util::remove_resource::<resource::BoardCellEntities>,
+                    util::remove_resource::<resource::AiTask>,
+                    util::remove_resource::<resource::Review>,
                 )
                     .chain(),
             )
@@ -57,42 +166,178 @@ impl Plugin for GamePlugin {
                 Update,
                 (
                     system::set_initial_player_cells, // todo: find out how to not run this in update loop
-                    system::button_interaction_system, // send event::CellClick
+                    system::button_interaction_system.run_if(not(system::is_board_locked)), // send event::CellClick
                     (
-                        system::change_clicked_player_cell,
-                        system::change_opposite_player_cells,
+                        system::history_keyboard_input,
+                        system::history_button_interaction,
                     )
-                        .chain()
-                        .run_if(
-                            on_event::<event::CellClick>().or_else(on_event::<event::AfterInit>()),
-                        ),
-                    util::send_default_event::<event::TurnChange>
-                        .run_if(on_event::<event::CellClick>()),
+                        .run_if(system::is_local_game.and_then(not(system::is_board_locked))), // send event::HistoryRequest
+                    (
+                        system::save_game_input,
+                        system::load_game_input
+                            .run_if(system::is_local_game.and_then(not(system::is_board_locked))),
+                    ), // load sends event::PlayerCellChanged and event::TurnChange
+                    (
+                        system::receive_network_move.run_if(
+                            resource_exists::<resource::NetworkPeer>()
+                                .and_then(not(system::is_board_locked)),
+                        ), // send event::CellClick
+                        system::receive_spectator_message.run_if(
+                            resource_exists::<resource::SpectatedGame>()
+                                .and_then(not(system::is_board_locked)),
+                        ), // send event::CellClick, event::PlayerCellChanged and event::TurnChange
+                        (
+                            system::replay_input,
+                            system::advance_replay.run_if(not(system::is_board_locked)), // send event::CellClick and event::TurnChange
+                        )
+                            .chain()
+                            .run_if(resource_exists::<resource::Replay>()),
+                    )
+                        .chain(),
+                    (
+                        (system::move_list_interaction, system::review_keyboard_input)
+                            .run_if(not(system::is_animating)), // insert or remove resource::Review
+                        system::show_review
+                            .run_if(resource_changed_or_removed::<resource::Review>()), // send event::PlayerCellChanged
+                    )
+                        .chain(),
+                    system::apply_history_request.run_if(on_event::<event::HistoryRequest>()), // send event::PlayerCellChanged and event::TurnChange
+                    system::finish_ai_turn.run_if(
+                        resource_exists::<resource::AiTask>()
+                            .and_then(not(system::is_board_locked)),
+                    ), // send event::CellClick
+                    system::change_clicked_player_cell.run_if(on_event::<event::CellClick>()), // send event::PlayerCellChanged, event::TurnChange and event::DiscsFlipped
+                    (
+                        system::change_player_cells.run_if(on_event::<event::PlayerCellChanged>()),
+                        system::start_flip_animations.run_if(on_event::<event::DiscsFlipped>()),
+                    )
+                        .chain(),
                     util::send_default_event::<event::TurnStuck>.run_if(
                         not(system::any_clickable_cell) // no cell to click
-                            .and_then(not(on_event::<event::AfterInit>())), // skip init
-                    ),
-                    system::update_turn.run_if(
-                        on_event::<event::TurnChange>().or_else(on_event::<event::TurnStuck>()),
+                            .and_then(not(on_event::<event::AfterInit>())) // skip init
+                            .and_then(not(system::is_watching)) // passes come from the spectated or replayed game
+                            .and_then(not(system::is_board_locked)), // the result waits for the last flips
                     ),
-                    (system::clear_cell_clickable, system::update_cell_clickable)
+                    system::pass_turn.run_if(on_event::<event::TurnStuck>()),
+                    (
+                        system::clear_cell_clickable,
+                        system::update_cell_clickable.run_if(
+                            not(system::is_watching)
+                                .and_then(not(resource_exists::<resource::Review>())),
+                        ), // read-only
+                        system::update_hints.run_if(resource_exists::<resource::Hints>()),
+                    )
                         .chain()
                         .run_if(
                             on_event::<event::TurnChange>()
                                 .or_else(on_event::<event::TurnStuck>())
                                 .or_else(on_event::<event::AfterInit>()),
                         ),
-                    system::change_cell_color,
+                    (
+                        system::toggle_hints, // marks every cell changed to recolor it
+                        system::preview_flips,
+                        system::change_cell_color,
+                        system::animate_flips,
+                        system::change_hint_text
+                            .run_if(resource_changed_or_removed::<resource::Hints>()),
+                        system::update_hud.run_if(
+                            resource_changed::<resource::GameData>()
+                                .or_else(on_event::<event::AfterInit>())
+                                .or_else(resource_added::<resource::NetworkLost>()),
+                        ),
+                        system::update_move_list.run_if(
+                            resource_changed::<resource::GameData>()
+                                .or_else(resource_changed_or_removed::<resource::Review>())
+                                .or_else(on_event::<event::AfterInit>()),
+                        ),
+                        system::scroll_move_list,
+                        system::update_replay_status
+                            .run_if(resource_exists_and_changed::<resource::Replay>()),
+                    )
+                        .chain(),
                     system::change_board_background_color,
                     system::check_win_condition.run_if(on_event::<event::TurnStuck>()),
+                    system::start_ai_turn.run_if(not(resource_exists::<resource::AiTask>())),
+                    system::broadcast_game
+                        .run_if(resource_exists::<resource::SpectatorBroadcaster>()),
                 )
                     .chain()
                     .run_if(in_state(GameState::Game)),
             );
 
+        if let Some(port) = self.spectator_port {
+            match spectator::Broadcaster::bind(port) {
+                Ok(broadcaster) => {
+                    app.insert_resource(resource::SpectatorBroadcaster(broadcaster));
+                }
+                Err(e) => error!("Cannot broadcast to spectators on port {}: {}", port, e),
+            }
+        }
+
+        if let Some(moves) = &self.replay {
+            // every move comes from the recording
+            let mut player_controllers = app.world.resource_mut::<resource::PlayerControllers>();
+            for turn in [data::Turn::Black, data::Turn::White] {
+                player_controllers
+                    .0
+                    .insert(turn, ai::PlayerController::Human);
+            }
+            app.insert_resource(resource::Replay(replay::Playback::new(moves.clone())));
+        }
+
+        if let Some(saved_game) = &self.saved_game {
+            app.insert_resource(resource::SavedGame(saved_game.clone()));
+        }
+
         #[cfg(feature = "debug")]
         {
             app.add_systems(Last, system::debug::auto_cell_click);
         }
     }
 }
+
+pub enum Connection {
+    Peer(network::Peer),
+    Spectator(spectator::Spectator),
+}
+
+// Plays with a player on the network or watches a game, added after GamePlugin.
+pub struct NetworkPlugin {
+    connection: Mutex<Option<Connection>>, // moved into the app when it's built
+}
+
+impl NetworkPlugin {
+    pub fn new(connection: Connection) -> Self {
+        NetworkPlugin {
+            connection: Mutex::new(Some(connection)),
+        }
+    }
+}
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut bevy::prelude::App) {
+        let Some(connection) = self.connection.lock().unwrap().take() else {
+            return;
+        };
+        let mut player_controllers = app.world.resource_mut::<resource::PlayerControllers>();
+        match connection {
+            Connection::Peer(peer) => {
+                // the remote turn is decided on the other side and arrives as clicks
+                let remote_turn = peer.local_turn().next();
+                player_controllers
+                    .0
+                    .insert(remote_turn, ai::PlayerController::Human);
+                app.insert_resource(resource::NetworkPeer(peer));
+            }
+            Connection::Spectator(spectator) => {
+                // both turns arrive from the spectated game
+                for turn in [data::Turn::Black, data::Turn::White] {
+                    player_controllers
+                        .0
+                        .insert(turn, ai::PlayerController::Human);
+                }
+                app.insert_resource(resource::SpectatedGame(spectator));
+            }
+        }
+    }
+}
@@ -0,0 +1,359 @@
+// Recorded games played back move by move, the transcript is checked against the rules first.
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::*;
+use data::{GameData, Move};
+
+pub const DEFAULT_SPEED: f32 = 1.; // moves per second
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 16.;
+const HEADER_PREFIX: char = '%';
+const LAYOUT_TAG: &str = "layout";
+const HANDICAP_TAG: &str = "handicap";
+
+// A recorded game, the start comes from the header and the moves are played from there.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Transcript {
+    pub layout: layout::InitialLayout,
+    pub handicap: Option<layout::Handicap>,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    IO(std::io::Error),
+    Header(serde_json::Error),
+    Notation(notation::NotationError),
+    Layout(layout::LayoutError),
+    IllegalMove {
+        index: usize,
+        m: Move, // as written in the transcript
+        error: rules::MoveError,
+    },
+    AfterGameOver(usize),
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::IO(e) => write!(f, "cannot open transcript: {}", e),
+            ReplayError::Header(e) => write!(f, "cannot read transcript header: {}", e),
+            ReplayError::Notation(e) => write!(f, "cannot read transcript: {:?}", e),
+            ReplayError::Layout(e) => write!(f, "cannot set up the board: {}", e),
+            // moves are counted from 1 like in the move list
+            ReplayError::IllegalMove { index, m, error } => write!(
+                f,
+                "move {} ({}) is illegal: {:?}",
+                index + 1,
+                notation::move_to_string(m),
+                error
+            ),
+            ReplayError::AfterGameOver(index) => {
+                write!(f, "move {} comes after the end of the game", index + 1)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for ReplayError {
+    fn from(value: std::io::Error) -> Self {
+        ReplayError::IO(value)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(value: serde_json::Error) -> Self {
+        ReplayError::Header(value)
+    }
+}
+
+impl From<notation::NotationError> for ReplayError {
+    fn from(value: notation::NotationError) -> Self {
+        ReplayError::Notation(value)
+    }
+}
+
+impl From<layout::LayoutError> for ReplayError {
+    fn from(value: layout::LayoutError) -> Self {
+        ReplayError::Layout(value)
+    }
+}
+
+// Plays every move on the game, stops at the first one the rules don't allow.
+pub fn validate(game_data: &GameData, moves: &[Move]) -> Result<(), ReplayError> {
+    let mut game_data = game_data.clone();
+    for (index, m) in moves.iter().enumerate() {
+        if game_data.is_game_over() {
+            return Err(ReplayError::AfterGameOver(index));
+        }
+        let result = match m {
+            Move::Place(position) => game_data.play(position).map(|_| ()),
+            Move::Pass => game_data.pass(),
+        };
+        result.map_err(|error| ReplayError::IllegalMove {
+            index,
+            m: notation::standard_move(m, game_data.board().size),
+            error,
+        })?;
+    }
+    Ok(())
+}
+
+fn header_line(tag: &str, value: &impl Serialize) -> String {
+    let value = serde_json::to_string(value).expect("layouts and handicaps are plain JSON");
+    format!("{} {} {}\n", HEADER_PREFIX, tag, value)
+}
+
+// The moves in standard notation. A start other than the standard one is written before them
+// in header lines like "% handicap {"player":"Black","corners":2}", so a plain game stays
+// readable by other tools.
+pub fn to_string(game_data: &GameData) -> String {
+    let mut text = String::new();
+    if *game_data.layout() != layout::InitialLayout::default() {
+        text += &header_line(LAYOUT_TAG, game_data.layout());
+    }
+    if let Some(handicap) = game_data.handicap() {
+        text += &header_line(HANDICAP_TAG, handicap);
+    }
+    text + &notation::standard_transcript_to_string(game_data.history(), game_data.board().size)
+}
+
+// Header lines with other tags are comments, a transcript without a header starts from the standard start.
+pub fn parse(text: &str, size: board::Size) -> Result<Transcript, ReplayError> {
+    let mut transcript = Transcript::default();
+    let mut moves = String::new();
+    for line in text.lines() {
+        let Some(header) = line.trim().strip_prefix(HEADER_PREFIX) else {
+            moves.push_str(line);
+            continue;
+        };
+        match header.trim().split_once(char::is_whitespace) {
+            Some((LAYOUT_TAG, value)) => transcript.layout = serde_json::from_str(value)?,
+            Some((HANDICAP_TAG, value)) => transcript.handicap = Some(serde_json::from_str(value)?),
+            _ => (),
+        }
+    }
+    transcript.moves = notation::parse_standard_transcript(&moves, size)?;
+    Ok(transcript)
+}
+
+pub fn load(path: &str, size: board::Size) -> Result<Transcript, ReplayError> {
+    parse(&std::fs::read_to_string(path)?, size)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    TogglePlaying,
+    Step,
+    Slower,
+    Faster,
+}
+
+// Where the playback is and how fast it goes.
+#[derive(Clone, Debug)]
+pub struct Playback {
+    moves: Vec<Move>,
+    next: usize, // index of the next move to play
+    playing: bool,
+    step_requested: bool,
+    speed: f32,
+    elapsed: Duration, // since the last move while playing
+}
+
+impl Playback {
+    pub fn new(moves: Vec<Move>) -> Self {
+        Playback {
+            moves,
+            next: 0,
+            playing: false,
+            step_requested: false,
+            speed: DEFAULT_SPEED,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.next = 0;
+        self.playing = false;
+        self.step_requested = false;
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn position(&self) -> usize {
+        self.next
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.moves.len()
+    }
+
+    pub fn apply(&mut self, control: Control) {
+        match control {
+            Control::TogglePlaying => {
+                self.playing = !self.playing && !self.is_finished();
+                self.elapsed = Duration::ZERO;
+            }
+            // a single move, the playback pauses for it
+            Control::Step => {
+                self.playing = false;
+                self.step_requested = true;
+            }
+            Control::Slower => self.speed = (self.speed / 2.).max(MIN_SPEED),
+            Control::Faster => self.speed = (self.speed * 2.).min(MAX_SPEED),
+        }
+    }
+
+    // The move to play now: a requested step, or the next one once its time has come while playing.
+    pub fn next_move(&mut self, delta: Duration) -> Option<Move> {
+        if std::mem::take(&mut self.step_requested) {
+            return self.take_move();
+        }
+        if !self.playing {
+            return None;
+        }
+        self.elapsed += delta;
+        let interval = Duration::from_secs_f32(1. / self.speed);
+        if self.elapsed < interval {
+            return None;
+        }
+        self.elapsed -= interval;
+        self.take_move()
+    }
+
+    fn take_move(&mut self) -> Option<Move> {
+        let m = self.moves.get(self.next).copied()?;
+        self.next += 1;
+        if self.is_finished() {
+            self.playing = false;
+        }
+        Some(m)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_game() -> GameData {
+        GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        )
+    }
+
+    fn moves(text: &str) -> Result<Vec<Move>, ReplayError> {
+        let moves = parse(text, board::Size::new(8, 8).unwrap())?.moves;
+        validate(&new_game(), &moves)?;
+        Ok(moves)
+    }
+
+    #[test]
+    fn reports_first_illegal_move() {
+        assert_eq!(moves("f5 d6 c3").unwrap().len(), 3);
+
+        match moves("f5d6c3a1c4") {
+            Err(ReplayError::IllegalMove { index, m, error }) => {
+                assert_eq!(index, 3);
+                assert_eq!(m, Move::Place(notation::parse("a1").unwrap()));
+                assert!(matches!(error, rules::MoveError::NoFlip(_)));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(
+            moves("f5pa"),
+            Err(ReplayError::IllegalMove {
+                index: 1,
+                error: rules::MoveError::HasLegalMove,
+                ..
+            })
+        ));
+        assert!(matches!(moves("f5z"), Err(ReplayError::Notation(_))));
+    }
+
+    #[test]
+    fn header() {
+        let size = board::Size::new(8, 8).unwrap();
+        let handicap = layout::Handicap {
+            player: data::Player::Black,
+            corners: 2,
+        };
+        let mut game_data = new_game().with_handicap(handicap).unwrap();
+        let m = game_data.legal_moves()[0];
+        game_data.play(&m).unwrap();
+        let text = game_data.transcript();
+        assert!(text.starts_with("% handicap "));
+
+        let transcript = parse(&text, size).unwrap();
+        assert_eq!(transcript.handicap, Some(handicap));
+        assert_eq!(transcript.layout, layout::InitialLayout::default());
+        assert_eq!(transcript.moves, game_data.history());
+        let start = new_game().with_handicap(handicap).unwrap();
+        assert!(validate(&start, &transcript.moves).is_ok());
+
+        let cross = layout::InitialLayout::Standard {
+            opening: layout::Opening::Cross,
+            quarter_turns: 1,
+        };
+        let game_data =
+            GameData::with_layout(data::Turn::Black, data::Board::new(size), cross.clone())
+                .unwrap();
+        let transcript = parse(&game_data.transcript(), size).unwrap();
+        assert_eq!(transcript.layout, cross);
+        assert_eq!(transcript.handicap, None);
+
+        assert_eq!(parse("% a comment\nf5", size).unwrap().moves.len(), 1);
+        assert!(matches!(
+            parse("% handicap {", size),
+            Err(ReplayError::Header(_))
+        ));
+    }
+
+    #[test]
+    fn playback() {
+        let moves = moves("f5d6c3").unwrap();
+        let mut playback = Playback::new(moves.clone());
+        assert_eq!(playback.next_move(Duration::from_secs(10)), None);
+
+        playback.apply(Control::Step);
+        assert_eq!(playback.next_move(Duration::ZERO), Some(moves[0]));
+        assert_eq!(playback.next_move(Duration::from_secs(10)), None);
+
+        playback.apply(Control::TogglePlaying);
+        playback.apply(Control::Faster);
+        assert_eq!(playback.next_move(Duration::from_millis(400)), None);
+        assert_eq!(
+            playback.next_move(Duration::from_millis(100)),
+            Some(moves[1])
+        );
+        assert_eq!(
+            playback.next_move(Duration::from_millis(500)),
+            Some(moves[2])
+        );
+        assert!(playback.is_finished());
+        assert!(!playback.is_playing());
+        playback.apply(Control::Step);
+        assert_eq!(playback.next_move(Duration::ZERO), None);
+
+        playback.restart();
+        assert_eq!(playback.position(), 0);
+        for _ in 0..10 {
+            playback.apply(Control::Slower);
+        }
+        assert_eq!(playback.speed(), MIN_SPEED);
+    }
+}
@@ -3,7 +3,7 @@ use bevy::{
     utils::HashMap,
 };
 
-use super::{board, data, util::IterEntity};
+use super::{ai, board, data, network, replay, spectator, util::IterEntity};
 
 #[derive(Resource, Clone, Deref)]
 pub struct BoardSettings(#[deref] pub data::BoardSettings);
@@ -22,6 +22,10 @@ impl From<data::GameData> for GameData {
     }
 }
 
+// A game loaded at startup, taken over when the game state is entered.
+#[derive(Resource, Deref, Debug)]
+pub struct SavedGame(pub data::GameData);
+
 #[derive(Resource, Deref, DerefMut, Debug, Default)]
 pub struct BoardCellEntities(HashMap<board::BoardPosition, Entity>);
 
@@ -31,3 +35,63 @@ impl IterEntity for BoardCellEntities {
         Box::new(iter)
     }
 }
+
+#[derive(Resource, Deref, Debug, Default)]
+pub struct PlayerControllers(pub HashMap<data::Turn, ai::PlayerController>);
+
+impl PlayerControllers {
+    pub fn is_human(&self, turn: &data::Turn) -> bool {
+        ai::is_human(self, turn)
+    }
+}
+
+// A computer player thinking in the background for the given turn.
+#[derive(Resource)]
+pub struct AiTask {
+    pub turn_count: u16,
+    pub handle: Option<std::thread::JoinHandle<Option<board::BoardPosition>>>,
+}
+
+// The other player on the network, see network::Peer.
+#[derive(Resource, Deref, DerefMut)]
+pub struct NetworkPeer(pub network::Peer);
+
+// The connection to the other player was lost, the board keeps the last position and takes no more moves.
+#[derive(Resource)]
+pub struct NetworkLost;
+
+// Sends the game to spectators, see spectator::Broadcaster.
+#[derive(Resource, Deref, DerefMut)]
+pub struct SpectatorBroadcaster(pub spectator::Broadcaster);
+
+// A game played elsewhere, shown read-only.
+#[derive(Resource, Deref, DerefMut)]
+pub struct SpectatedGame(pub spectator::Spectator);
+
+// A recorded game played back on the board, shown read-only.
+#[derive(Resource, Deref, DerefMut, Debug)]
+pub struct Replay(pub replay::Playback);
+
+// The board shows the position after this many moves of the history instead of the live game.
+#[derive(Resource, Deref, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Review(pub usize);
+
+#[derive(Resource, Deref, Debug, Default)]
+pub struct HintSettings(pub ai::HintSettings);
+
+// Scores of the legal moves while the hint overlay is shown.
+#[derive(Resource, Deref, DerefMut, Debug, Default)]
+pub struct Hints(HashMap<board::BoardPosition, i32>);
+
+impl Hints {
+    // From 0 for the worst move to 1 for the best one.
+    pub fn rank(&self, position: &board::BoardPosition) -> Option<f32> {
+        let score = *self.get(position)?;
+        let best = *self.values().max()?;
+        let worst = *self.values().min()?;
+        if best == worst {
+            return Some(1.);
+        }
+        Some((score - worst) as f32 / (best - worst) as f32)
+    }
+}
@@ -0,0 +1,576 @@
+// Reversi rules on top of data::Board, free of any Bevy world access.
+// Systems in system.rs are adapters over these functions.
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use data::{Board, Player};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    NoPlayer,
+    OutOfBoard(board::BoardPosition),
+    Blocked(board::BoardPosition),
+    Occupied(board::BoardPosition),
+    NoFlip(board::BoardPosition),
+    HasLegalMove,
+}
+
+pub type MoveResult = Result<Vec<board::BoardPosition>, MoveError>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub black: u16,
+    pub white: u16,
+}
+
+impl Score {
+    pub fn get(&self, player: &Player) -> u16 {
+        match player {
+            Player::Black => self.black,
+            Player::White => self.white,
+            Player::None => 0,
+        }
+    }
+
+    // Player::None means a draw.
+    pub fn winner(&self) -> Player {
+        use std::cmp::Ordering::*;
+        match self.black.cmp(&self.white) {
+            Greater => Player::Black,
+            Less => Player::White,
+            Equal => Player::None,
+        }
+    }
+}
+
+// How the final score decides the game, anti-reversi is won with fewer discs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleSet {
+    #[default]
+    Standard,
+    Anti,
+}
+
+impl std::fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            RuleSet::Standard => "Standard",
+            RuleSet::Anti => "Anti-reversi",
+        };
+        f.write_str(str)
+    }
+}
+
+impl RuleSet {
+    // Player::None means a draw.
+    pub fn winner(&self, score: &Score) -> Player {
+        match self {
+            RuleSet::Standard => score.winner(),
+            RuleSet::Anti => score.winner().next(),
+        }
+    }
+
+    // A value that is higher the more discs player has, turned into one that is higher the better player does.
+    pub fn objective(&self, value: i32) -> i32 {
+        match self {
+            RuleSet::Standard => value,
+            RuleSet::Anti => -value,
+        }
+    }
+}
+
+// The standard 2x2 start in the middle of the board.
+// Along an odd side the block covers the middle cell and the one before it, c3 and d4 are black on 7x7.
+pub fn initial_cells(size: board::Size) -> [(board::BoardPosition, Player); 4] {
+    const INDEX_OFFSET: u16 = 1;
+    let starting_point = (
+        ((size.x() / 2) - INDEX_OFFSET) as board::PositionUnit,
+        ((size.y() / 2) - INDEX_OFFSET) as board::PositionUnit,
+    );
+    [
+        ((starting_point.0, starting_point.1).into(), Player::Black),
+        (
+            (starting_point.0 + 1, starting_point.1).into(),
+            Player::White,
+        ),
+        (
+            (starting_point.0, starting_point.1 + 1).into(),
+            Player::White,
+        ),
+        (
+            (starting_point.0 + 1, starting_point.1 + 1).into(),
+            Player::Black,
+        ),
+    ]
+}
+
+pub fn set_initial_cells(board: &mut Board) {
+    for (pos, player) in initial_cells(board.size) {
+        if let Some(cell) = board.cell_mut(&pos) {
+            *cell = player;
+        }
+    }
+}
+
+// Opposite cells that would be flipped if player placed a disc on position.
+// Empty if the move isn't legal.
+pub fn flips(
+    board: &Board,
+    position: &board::BoardPosition,
+    player: Player,
+) -> Vec<board::BoardPosition> {
+    if player == Player::None || board.cell_ref(position) != Some(&Player::None) {
+        return vec![];
+    }
+    let opposite_player = player.next();
+
+    board::DIRECTIONS
+        .iter()
+        .flat_map(|direction| {
+            let mut iter = board::Iter::new(board, *position, *direction, 1);
+            let mut opposite_positions: Vec<board::BoardPosition> = vec![];
+            let found_same_player_on_other_side = loop {
+                match iter.next() {
+                    Some((pos, p)) if p.eq(&opposite_player) => {
+                        opposite_positions.push(pos);
+                    }
+                    Some((_, p)) if p.eq(&player) => {
+                        break true;
+                    }
+                    _ => break false,
+                }
+            };
+
+            if found_same_player_on_other_side {
+                opposite_positions
+            } else {
+                vec![]
+            }
+        })
+        .collect()
+}
+
+pub fn is_legal_move(board: &Board, position: &board::BoardPosition, player: Player) -> bool {
+    !flips(board, position, player).is_empty()
+}
+
+pub fn legal_moves(board: &Board, player: Player) -> Vec<board::BoardPosition> {
+    let mut moves: Vec<_> = board
+        .cells
+        .iter()
+        .filter(|(_, cell)| **cell == Player::None)
+        .map(|(pos, _)| *pos)
+        .filter(|pos| is_legal_move(board, pos, player))
+        .collect();
+    // keep the order stable, HashMap iteration isn't
+    moves.sort_by_key(|pos| (pos.y, pos.x));
+    moves
+}
+
+pub fn has_legal_move(board: &Board, player: Player) -> bool {
+    board
+        .cells
+        .iter()
+        .filter(|(_, cell)| **cell == Player::None)
+        .any(|(pos, _)| is_legal_move(board, pos, player))
+}
+
+// Place a disc and flip the opposite cells, returns the flipped positions.
+pub fn apply_move(
+    board: &mut Board,
+    position: &board::BoardPosition,
+    player: Player,
+) -> MoveResult {
+    match board.cell_ref(position) {
+        _ if player == Player::None => return Err(MoveError::NoPlayer),
+        None if board.is_blocked(position) => return Err(MoveError::Blocked(*position)),
+        None => return Err(MoveError::OutOfBoard(*position)),
+        Some(Player::Black | Player::White) => return Err(MoveError::Occupied(*position)),
+        Some(Player::None) => (),
+    }
+
+    let flipped = flips(board, position, player);
+    if flipped.is_empty() {
+        return Err(MoveError::NoFlip(*position));
+    }
+
+    for pos in std::iter::once(position).chain(flipped.iter()) {
+        *board.cell_mut(pos).unwrap() = player;
+    }
+    Ok(flipped)
+}
+
+pub fn is_game_over(board: &Board) -> bool {
+    !has_legal_move(board, Player::Black) && !has_legal_move(board, Player::White)
+}
+
+pub fn score(board: &Board) -> Score {
+    board
+        .cells
+        .values()
+        .fold(Score::default(), |mut score, cell| {
+            match cell {
+                Player::Black => score.black += 1,
+                Player::White => score.white += 1,
+                Player::None => (),
+            }
+            score
+        })
+}
+
+// Common interface of the board representations, so search code can run on either.
+pub trait Position: Clone {
+    fn size(&self) -> board::Size;
+
+    fn cell(&self, position: &board::BoardPosition) -> Option<Player>;
+
+    fn legal_moves(&self, player: Player) -> Vec<board::BoardPosition>;
+
+    fn has_legal_move(&self, player: Player) -> bool {
+        !self.legal_moves(player).is_empty()
+    }
+
+    fn apply_move(&mut self, position: &board::BoardPosition, player: Player) -> MoveResult;
+
+    fn score(&self) -> Score;
+
+    fn empty_count(&self) -> u16;
+
+    #[allow(dead_code)]
+    fn is_game_over(&self) -> bool {
+        !self.has_legal_move(Player::Black) && !self.has_legal_move(Player::White)
+    }
+}
+
+impl Position for Board {
+    fn size(&self) -> board::Size {
+        self.size
+    }
+
+    fn cell(&self, position: &board::BoardPosition) -> Option<Player> {
+        self.cell_ref(position).copied()
+    }
+
+    fn legal_moves(&self, player: Player) -> Vec<board::BoardPosition> {
+        legal_moves(self, player)
+    }
+
+    fn has_legal_move(&self, player: Player) -> bool {
+        has_legal_move(self, player)
+    }
+
+    fn apply_move(&mut self, position: &board::BoardPosition, player: Player) -> MoveResult {
+        apply_move(self, position, player)
+    }
+
+    fn score(&self) -> Score {
+        score(self)
+    }
+
+    fn empty_count(&self) -> u16 {
+        self.cells
+            .values()
+            .filter(|cell| **cell == Player::None)
+            .count() as u16
+    }
+}
+
+// Picks the bitboard for 8x8 boards and falls back to data::Board for other sizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchBoard {
+    Bit(bitboard::BitBoard),
+    Map(Board),
+}
+
+impl From<&Board> for SearchBoard {
+    fn from(board: &Board) -> Self {
+        match bitboard::BitBoard::try_from(board) {
+            Ok(bit_board) => SearchBoard::Bit(bit_board),
+            Err(_) => SearchBoard::Map(board.clone()),
+        }
+    }
+}
+
+impl From<&SearchBoard> for Board {
+    fn from(search_board: &SearchBoard) -> Self {
+        match search_board {
+            SearchBoard::Bit(bit_board) => bit_board.into(),
+            SearchBoard::Map(board) => board.clone(),
+        }
+    }
+}
+
+impl Position for SearchBoard {
+    fn size(&self) -> board::Size {
+        match self {
+            SearchBoard::Bit(b) => b.size(),
+            SearchBoard::Map(b) => b.size(),
+        }
+    }
+
+    fn cell(&self, position: &board::BoardPosition) -> Option<Player> {
+        match self {
+            SearchBoard::Bit(b) => b.cell(position),
+            SearchBoard::Map(b) => b.cell(position),
+        }
+    }
+
+    fn legal_moves(&self, player: Player) -> Vec<board::BoardPosition> {
+        match self {
+            SearchBoard::Bit(b) => b.legal_moves(player),
+            SearchBoard::Map(b) => Position::legal_moves(b, player),
+        }
+    }
+
+    fn has_legal_move(&self, player: Player) -> bool {
+        match self {
+            SearchBoard::Bit(b) => b.has_legal_move(player),
+            SearchBoard::Map(b) => Position::has_legal_move(b, player),
+        }
+    }
+
+    fn apply_move(&mut self, position: &board::BoardPosition, player: Player) -> MoveResult {
+        match self {
+            SearchBoard::Bit(b) => b.apply_move(position, player),
+            SearchBoard::Map(b) => Position::apply_move(b, position, player),
+        }
+    }
+
+    fn score(&self) -> Score {
+        match self {
+            SearchBoard::Bit(b) => b.score(),
+            SearchBoard::Map(b) => Position::score(b),
+        }
+    }
+
+    fn empty_count(&self) -> u16 {
+        match self {
+            SearchBoard::Bit(b) => b.empty_count(),
+            SearchBoard::Map(b) => b.empty_count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn new_board() -> Board {
+        let mut board = Board::new(board::Size::new(8, 8).unwrap());
+        set_initial_cells(&mut board);
+        board
+    }
+
+    #[test]
+    fn initial_legal_moves() {
+        let board = new_board();
+        let moves = legal_moves(&board, Player::Black);
+        let expected: Vec<board::BoardPosition> =
+            vec![(4, 2).into(), (5, 3).into(), (2, 4).into(), (3, 5).into()];
+        assert_eq!(moves, expected);
+    }
+
+    #[test]
+    fn apply_flips() {
+        let mut board = new_board();
+        let flipped = apply_move(&mut board, &(4, 2).into(), Player::Black).unwrap();
+        assert_eq!(flipped, vec![(4, 3).into()]);
+        assert_eq!(score(&board), Score { black: 4, white: 1 });
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let mut board = new_board();
+        assert_eq!(
+            apply_move(&mut board, &(3, 3).into(), Player::Black),
+            Err(MoveError::Occupied((3, 3).into()))
+        );
+        assert_eq!(
+            apply_move(&mut board, &(0, 0).into(), Player::Black),
+            Err(MoveError::NoFlip((0, 0).into()))
+        );
+        assert_eq!(
+            apply_move(&mut board, &(8, 0).into(), Player::Black),
+            Err(MoveError::OutOfBoard((8, 0).into()))
+        );
+    }
+
+    #[test]
+    fn blocked_cells() {
+        let mut board = new_board();
+        let e3 = notation::parse("e3").unwrap();
+        board.block(&e3);
+        assert_eq!(
+            notation::sequence_to_string(&legal_moves(&board, Player::Black)),
+            "f4c5d6"
+        );
+        assert_eq!(
+            apply_move(&mut board, &e3, Player::Black),
+            Err(MoveError::Blocked(e3))
+        );
+        assert!(matches!(SearchBoard::from(&board), SearchBoard::Map(_)));
+    }
+
+    #[test]
+    fn rectangular_and_odd_boards() {
+        assert_eq!(
+            data::BoardSize::try_from(3).unwrap_err(),
+            data::BoardSizeError::TooSmall(3)
+        );
+        assert!(serde_json::from_str::<data::BoardSize>("27").is_err());
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for (x, y, white) in [
+            (6, 10, ["d5", "c6"]),
+            (7, 7, ["d3", "c4"]),
+            (9, 4, ["e2", "d3"]),
+        ] {
+            let mut game_data = data::GameData::new(
+                data::Turn::Black,
+                x.try_into().unwrap(),
+                y.try_into().unwrap(),
+            );
+            let white_cells: Vec<_> = initial_cells(game_data.board().size)
+                .into_iter()
+                .filter(|(_, player)| *player == Player::White)
+                .map(|(pos, _)| notation::to_string(&pos))
+                .collect();
+            assert_eq!(white_cells, white);
+
+            while !game_data.is_game_over() {
+                let moves = game_data.legal_moves();
+                assert!(moves
+                    .iter()
+                    .all(|pos| pos.x < x as i64 && pos.y < y as i64 && pos.x >= 0 && pos.y >= 0));
+                if moves.is_empty() {
+                    game_data.pass().unwrap();
+                } else {
+                    let pos = moves[rng.gen_range(0..moves.len())];
+                    game_data.play(&pos).unwrap();
+                }
+            }
+            let score = score(game_data.board());
+            assert!(score.black + score.white <= x * y);
+        }
+    }
+
+    #[test]
+    fn rule_sets() {
+        let score = Score {
+            black: 40,
+            white: 24,
+        };
+        assert_eq!(RuleSet::Standard.winner(&score), Player::Black);
+        assert_eq!(RuleSet::Anti.winner(&score), Player::White);
+        let draw = Score {
+            black: 32,
+            white: 32,
+        };
+        assert_eq!(RuleSet::Anti.winner(&draw), Player::None);
+        assert_eq!(RuleSet::Standard.objective(3), 3);
+        assert_eq!(RuleSet::Anti.objective(3), -3);
+    }
+
+    #[test]
+    fn full_random_game() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut game_data = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        while !game_data.is_game_over() {
+            let moves = game_data.legal_moves();
+            if moves.is_empty() {
+                game_data.pass().unwrap();
+            } else {
+                let pos = moves[rng.gen_range(0..moves.len())];
+                game_data.play(&pos).unwrap();
+            }
+        }
+        let score = score(game_data.board());
+        assert!(score.black + score.white <= 64);
+        assert_eq!(game_data.legal_moves(), vec![]);
+
+        // the transcript plays the same game again
+        let moves =
+            notation::parse_standard_transcript(&game_data.transcript(), game_data.board().size)
+                .unwrap();
+        assert_eq!(moves, game_data.history());
+        let mut replay = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        for m in moves {
+            match m {
+                data::Move::Place(pos) => replay.play(&pos).map(|_| ()),
+                data::Move::Pass => replay.pass(),
+            }
+            .unwrap();
+        }
+        assert_eq!(replay.board(), game_data.board());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut game_data = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        assert!(!game_data.undo());
+        for pos in notation::parse_sequence("c5e6f3").unwrap() {
+            game_data.play(&pos).unwrap();
+        }
+        let board = game_data.board().clone();
+
+        assert!(game_data.undo());
+        assert!(game_data.undo());
+        assert_eq!(game_data.transcript(), "f5");
+        assert_eq!(game_data.turn(), &data::Turn::White);
+        assert_eq!(game_data.turn_count(), 1);
+
+        assert!(game_data.redo());
+        assert!(game_data.redo());
+        assert!(!game_data.redo());
+        assert_eq!(game_data.board(), &board);
+        assert_eq!(game_data.transcript(), "f5d6c3");
+
+        // a new move drops what was undone
+        game_data.undo();
+        game_data.play(&notation::parse("f5").unwrap()).unwrap();
+        assert!(!game_data.can_redo());
+        assert_eq!(game_data.transcript(), "f5d6c5");
+    }
+
+    #[test]
+    fn after_moves() {
+        let mut game_data = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        for pos in notation::parse_sequence("c5e6f3").unwrap() {
+            game_data.play(&pos).unwrap();
+        }
+        let earlier = game_data.after_moves(1);
+        assert_eq!(earlier.transcript(), "f5");
+        assert_eq!(earlier.turn(), &data::Turn::White);
+        assert!(!earlier.can_redo());
+        assert_eq!(game_data.after_moves(10).board(), game_data.board());
+        assert_eq!(game_data.transcript(), "f5d6c3");
+    }
+
+    #[test]
+    fn search_board_selection() {
+        assert!(matches!(
+            SearchBoard::from(&new_board()),
+            SearchBoard::Bit(_)
+        ));
+        let board = Board::new(board::Size::new(6, 6).unwrap());
+        assert!(matches!(SearchBoard::from(&board), SearchBoard::Map(_)));
+    }
+}
@@ -0,0 +1,333 @@
+// Games in progress saved as versioned JSON files.
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+pub const SAVE_VERSION: u32 = 1;
+pub const DEFAULT_SAVE_PATH: &str = "save_game.json";
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    game_data: data::GameData,
+}
+
+// Read first, so a file from another version is reported as such and not as broken JSON.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    IO(std::io::Error),
+    Read(serde_json::Error),
+    UnsupportedVersion(u32),
+    InconsistentHistory,
+    Layout(layout::LayoutError),
+    BoardSizeMismatch(board::Size),
+    BlockedCellsMismatch,
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::IO(e) => write!(f, "cannot open save file: {}", e),
+            SaveError::Read(e) => write!(f, "cannot read save file: {}", e),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save file version {} is not supported, expected {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::InconsistentHistory => {
+                write!(f, "the move history doesn't lead to the saved board")
+            }
+            SaveError::Layout(e) => write!(f, "the saved start position is invalid: {}", e),
+            SaveError::BoardSizeMismatch(size) => write!(
+                f,
+                "the saved board is {}x{}, which isn't the board in the settings",
+                size.x(),
+                size.y()
+            ),
+            SaveError::BlockedCellsMismatch => {
+                write!(
+                    f,
+                    "the saved board has other blocked cells than the settings"
+                )
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(value: std::io::Error) -> Self {
+        SaveError::IO(value)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(value: serde_json::Error) -> Self {
+        SaveError::Read(value)
+    }
+}
+
+pub fn to_json(game_data: &data::GameData) -> Result<String, SaveError> {
+    let save_file = SaveFile {
+        version: SAVE_VERSION,
+        game_data: game_data.clone(),
+    };
+    Ok(serde_json::to_string_pretty(&save_file)?)
+}
+
+pub fn from_json(text: &str) -> Result<data::GameData, SaveError> {
+    let header: SaveHeader = serde_json::from_str(text)?;
+    if header.version != SAVE_VERSION {
+        return Err(SaveError::UnsupportedVersion(header.version));
+    }
+    let save_file: SaveFile = serde_json::from_str(text)?;
+    check_history(&save_file.game_data)?;
+    Ok(save_file.game_data)
+}
+
+// Undo and review replay the history from the start, it has to lead to the board.
+pub fn check_history(game_data: &data::GameData) -> Result<(), SaveError> {
+    match game_data.is_consistent() {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(SaveError::InconsistentHistory),
+        Err(e) => Err(SaveError::Layout(e)),
+    }
+}
+
+pub fn save(path: &str, game_data: &data::GameData) -> Result<(), SaveError> {
+    std::fs::write(path, to_json(game_data)?)?;
+    Ok(())
+}
+
+pub fn load(path: &str) -> Result<data::GameData, SaveError> {
+    from_json(&std::fs::read_to_string(path)?)
+}
+
+// The cells are spawned from the settings, a saved game has to fit them.
+pub fn check_board(
+    game_data: &data::GameData,
+    board_settings: &data::BoardSettings,
+) -> Result<(), SaveError> {
+    let size = game_data.board().size;
+    if size.x() != board_settings.board_size_x().size()
+        || size.y() != board_settings.board_size_y().size()
+    {
+        return Err(SaveError::BoardSizeMismatch(size));
+    }
+    let blocked_cells = |board: &data::Board| -> Vec<board::BoardPosition> {
+        position_pairs(size.x(), size.y())
+            .into_iter()
+            .map(Into::into)
+            .filter(|position| board.is_blocked(position))
+            .collect()
+    };
+    match board_settings.empty_board() {
+        Ok(board) if blocked_cells(&board) == blocked_cells(game_data.board()) => Ok(()),
+        _ => Err(SaveError::BlockedCellsMismatch),
+    }
+}
+
+// The board as one string per row from a1, 'X' for black, 'O' for white, '-' for empty and '#' for blocked.
+pub(super) mod board_rows {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::*;
+    use data::{Board, Player};
+
+    pub fn serialize<S: Serializer>(board: &Board, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<String> = (0..board.size.y() as board::PositionUnit)
+            .map(|y| {
+                (0..board.size.x() as board::PositionUnit)
+                    .map(|x| match board.cell_ref(&(x, y).into()) {
+                        Some(Player::Black) => 'X',
+                        Some(Player::White) => 'O',
+                        Some(Player::None) => '-',
+                        None => '#',
+                    })
+                    .collect()
+            })
+            .collect();
+        serializer.collect_seq(rows)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        let rows = Vec::<String>::deserialize(deserializer)?;
+        let size_x = rows
+            .first()
+            .map(|row| row.chars().count())
+            .unwrap_or_default();
+        if rows.iter().any(|row| row.chars().count() != size_x) {
+            return Err(D::Error::custom("board rows have different lengths"));
+        }
+        let size = board::Size::new(size_x as u16, rows.len() as u16)
+            .map_err(|e| D::Error::custom(format!("invalid board size: {:?}", e)))?;
+        let mut board = Board::new(size);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let player = match c {
+                    'X' => Player::Black,
+                    'O' => Player::White,
+                    '-' => Player::None,
+                    '#' => {
+                        board.block(&(x as i64, y as i64).into());
+                        continue;
+                    }
+                    c => return Err(D::Error::custom(format!("invalid cell '{}'", c))),
+                };
+                *board.cell_mut(&(x as i64, y as i64).into()).unwrap() = player;
+            }
+        }
+        Ok(board)
+    }
+}
+
+// Moves written as the standard notation transcript.
+pub(super) mod transcript {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::*;
+    use data::Move;
+
+    pub fn serialize<S: Serializer>(moves: &[Move], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&notation::transcript_to_string(moves))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Move>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        notation::parse_transcript(&text)
+            .map_err(|e| D::Error::custom(format!("invalid transcript: {:?}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn game_after(moves: &str) -> data::GameData {
+        let mut game_data = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        for pos in notation::parse_sequence(moves).unwrap() {
+            game_data.play(&pos).unwrap();
+        }
+        game_data
+    }
+
+    #[test]
+    fn round_trip() {
+        let game_data = game_after("c5e6f3e3f4");
+        let loaded = from_json(&to_json(&game_data).unwrap()).unwrap();
+        assert_eq!(loaded.board(), game_data.board());
+        assert_eq!(loaded.turn(), game_data.turn());
+        assert_eq!(loaded.turn_count(), game_data.turn_count());
+        assert_eq!(loaded.history(), game_data.history());
+        assert_eq!(loaded.rule_set(), rules::RuleSet::Standard);
+
+        let anti = game_data.with_rule_set(rules::RuleSet::Anti);
+        let loaded = from_json(&to_json(&anti).unwrap()).unwrap();
+        assert_eq!(loaded.rule_set(), rules::RuleSet::Anti);
+        assert_eq!(loaded.winner(), anti.score().winner().next());
+    }
+
+    #[test]
+    fn blocked_cells() {
+        let board_settings = data::BoardSettings::new(
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+            bevy::prelude::Color::RED,
+            std::iter::empty(),
+            bevy::prelude::Color::GRAY,
+            std::iter::empty(),
+            std::time::Duration::ZERO,
+        );
+        let blocked_settings = board_settings
+            .clone()
+            .with_blocked_cells(vec!["a1".to_string(), "h8".to_string()]);
+        let mut game_data = blocked_settings.new_game(data::Turn::Black).unwrap();
+        game_data.play(&notation::parse("c5").unwrap()).unwrap();
+
+        let json = to_json(&game_data).unwrap();
+        assert!(json.contains("\"#-------\""));
+        let loaded = from_json(&json).unwrap();
+        assert_eq!(loaded.board(), game_data.board());
+        assert!(check_board(&loaded, &blocked_settings).is_ok());
+        assert!(matches!(
+            check_board(&loaded, &board_settings),
+            Err(SaveError::BlockedCellsMismatch)
+        ));
+
+        // undo starts over from the same holes
+        let mut undone = loaded.clone();
+        assert!(undone.undo());
+        assert!(undone.board().is_blocked(&(7, 7).into()));
+    }
+
+    #[test]
+    fn handicap() {
+        let handicap = layout::Handicap {
+            player: data::Player::Black,
+            corners: 2,
+        };
+        let mut game_data = game_after("").with_handicap(handicap).unwrap();
+        assert_eq!(game_data.turn(), &data::Turn::White);
+        game_data.play(&notation::parse("e6").unwrap()).unwrap();
+
+        let json = to_json(&game_data).unwrap();
+        assert!(json.contains("\"handicap\""));
+        assert!(!to_json(&game_after("c5")).unwrap().contains("\"handicap\""));
+        let mut loaded = from_json(&json).unwrap();
+        assert_eq!(loaded.handicap(), Some(&handicap));
+        assert_eq!(loaded.board(), game_data.board());
+
+        // undo starts over from the handicap discs
+        assert!(loaded.undo());
+        assert_eq!(loaded.turn(), &data::Turn::White);
+        assert_eq!(
+            loaded.board().cell_ref(&(0, 0).into()),
+            Some(&data::Player::Black)
+        );
+        assert_eq!(
+            loaded.board().cell_ref(&(7, 7).into()),
+            Some(&data::Player::Black)
+        );
+        assert_eq!(
+            loaded.board().cell_ref(&(7, 0).into()),
+            Some(&data::Player::None)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let json = to_json(&game_after("c5e6")).unwrap();
+        let other_version = json.replacen(
+            &format!("\"version\": {}", SAVE_VERSION),
+            "\"version\": 0",
+            1,
+        );
+        assert!(matches!(
+            from_json(&other_version),
+            Err(SaveError::UnsupportedVersion(0))
+        ));
+
+        let other_history = json.replacen("c5e6", "c5c6", 1);
+        assert!(matches!(
+            from_json(&other_history),
+            Err(SaveError::InconsistentHistory)
+        ));
+
+        assert!(matches!(from_json("{}"), Err(SaveError::Read(_))));
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["game_data"]["layout"] = serde_json::json!({ "Cells": [["z9", "Black"]] });
+        assert!(matches!(
+            from_json(&value.to_string()),
+            Err(SaveError::Layout(layout::LayoutError::OutOfBoard(_)))
+        ));
+    }
+}
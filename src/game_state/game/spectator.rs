@@ -0,0 +1,194 @@
+// Live games broadcast to spectators as JSON lines: the whole game when a spectator
+// connects, then every move and pass, and the result at the end.
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+use network::{LineStream, NetworkError};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum SpectatorMessage {
+    Snapshot(data::GameData),
+    Move(data::Move),
+    Result(result::data::ResultData),
+}
+
+pub struct Broadcaster {
+    listener: TcpListener,
+    spectators: Vec<LineStream>,
+    sent_history: Vec<data::Move>, // the moves the spectators have seen
+}
+
+impl Broadcaster {
+    pub fn bind(port: u16) -> Result<Self, NetworkError> {
+        Self::from_listener(TcpListener::bind(("0.0.0.0", port))?)
+    }
+
+    pub fn from_listener(listener: TcpListener) -> Result<Self, NetworkError> {
+        listener.set_nonblocking(true)?;
+        Ok(Broadcaster {
+            listener,
+            spectators: vec![],
+            sent_history: vec![],
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn spectator_count(&self) -> usize {
+        self.spectators.len()
+    }
+
+    // Sends the moves played since the last call, then the whole game to new spectators.
+    pub fn update(&mut self, game_data: &data::GameData) -> Result<(), NetworkError> {
+        self.send_history(game_data)?;
+        self.accept(game_data)
+    }
+
+    fn accept(&mut self, game_data: &data::GameData) -> Result<(), NetworkError> {
+        let line = network::to_line(&SpectatorMessage::Snapshot(game_data.clone()))?;
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, address)) => {
+                    bevy::prelude::info!("spectator joined from {}", address);
+                    stream
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+            let mut spectator = LineStream::new(stream);
+            // a spectator that can't keep up is dropped instead of stalling the game
+            spectator.set_nonblocking(true)?;
+            if spectator.send_line(&line).is_ok() {
+                self.spectators.push(spectator);
+            }
+        }
+    }
+
+    // The whole game is sent again when it was rewound or replaced.
+    fn send_history(&mut self, game_data: &data::GameData) -> Result<(), NetworkError> {
+        let history = game_data.history();
+        if history.starts_with(&self.sent_history) {
+            for m in &history[self.sent_history.len()..] {
+                self.broadcast(&SpectatorMessage::Move(*m))?;
+            }
+        } else {
+            self.broadcast(&SpectatorMessage::Snapshot(game_data.clone()))?;
+        }
+        self.sent_history = history.to_vec();
+        Ok(())
+    }
+
+    pub fn broadcast(&mut self, message: &SpectatorMessage) -> Result<(), NetworkError> {
+        let line = network::to_line(message)?;
+        self.spectators
+            .retain_mut(|spectator| spectator.send_line(&line).is_ok());
+        Ok(())
+    }
+}
+
+// The receiving end, shows a game without playing it.
+pub struct Spectator {
+    stream: Option<LineStream>, // None once the game has gone away
+}
+
+impl Spectator {
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, NetworkError> {
+        let stream = LineStream::new(TcpStream::connect(address)?);
+        stream.set_nonblocking(true)?;
+        Ok(Spectator {
+            stream: Some(stream),
+        })
+    }
+
+    // The next message, None when nothing has arrived yet. The connection is closed after an error.
+    pub fn receive(&mut self) -> Result<Option<SpectatorMessage>, NetworkError> {
+        let Some(stream) = &mut self.stream else {
+            return Ok(None);
+        };
+        let message = stream.receive();
+        if message.is_err() {
+            self.stream = None;
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wait_for_message(spectator: &mut Spectator) -> SpectatorMessage {
+        loop {
+            if let Some(message) = spectator.receive().unwrap() {
+                return message;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn spectators_follow_the_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut broadcaster = Broadcaster::from_listener(listener).unwrap();
+        let mut game_data = data::GameData::new(
+            data::Turn::Black,
+            8.try_into().unwrap(),
+            8.try_into().unwrap(),
+        );
+        game_data.play(&notation::parse("c5").unwrap()).unwrap();
+
+        let mut spectators: Vec<Spectator> = (0..2)
+            .map(|_| Spectator::connect(address).unwrap())
+            .collect();
+        while broadcaster.spectator_count() < spectators.len() {
+            broadcaster.update(&game_data).unwrap();
+        }
+        for spectator in spectators.iter_mut() {
+            match wait_for_message(spectator) {
+                SpectatorMessage::Snapshot(snapshot) => {
+                    assert_eq!(snapshot.board(), game_data.board());
+                    assert_eq!(snapshot.transcript(), "f5");
+                }
+                message => panic!("{:?}", message),
+            }
+        }
+
+        let e6 = notation::parse("e6").unwrap();
+        game_data.play(&e6).unwrap();
+        broadcaster.update(&game_data).unwrap();
+        game_data.undo();
+        broadcaster.update(&game_data).unwrap();
+        let result = result::data::ResultData {
+            scores: [(result::data::PlayerType::Black, 4)].into_iter().collect(),
+            rule_set: rules::RuleSet::Anti,
+        };
+        broadcaster
+            .broadcast(&SpectatorMessage::Result(result))
+            .unwrap();
+
+        let spectator = &mut spectators[0];
+        assert!(matches!(
+            wait_for_message(spectator),
+            SpectatorMessage::Move(data::Move::Place(position)) if position == e6
+        ));
+        assert!(matches!(
+            wait_for_message(spectator),
+            SpectatorMessage::Snapshot(snapshot) if snapshot.transcript() == "f5"
+        ));
+        assert!(matches!(
+            wait_for_message(spectator),
+            SpectatorMessage::Result(result) if result.scores.len() == 1
+                && result.rule_set == rules::RuleSet::Anti
+        ));
+
+        drop(spectators);
+        while broadcaster.spectator_count() > 0 {
+            broadcaster
+                .broadcast(&SpectatorMessage::Move(data::Move::Pass))
+                .unwrap();
+        }
+    }
+}
@@ -1,28 +1,62 @@
 use crate::board;
 use bevy::prelude::*;
-use itertools::Itertools;
 
 use super::position_pairs;
-use bevy::utils::HashMap;
 use std::ops::{Deref, DerefMut};
 
 pub use data::{Board, BoardCell, BoardSettings, Player};
 
 use super::*;
 
-pub fn init_game_data(mut game_data: ResMut<resource::GameData>) {
-    game_data.reset();
+const FONT_PATH: &str = "fonts/NotoSans-Regular.ttf";
+const CONTROL_FONT_SIZE: f32 = 30.;
+const HINT_FONT_SIZE: f32 = 20.;
+const UNDO_KEYCODE: KeyCode = KeyCode::Z; // with ctrl, shift turns it into redo
+const REDO_KEYCODE: KeyCode = KeyCode::Y; // with ctrl
+const SAVE_KEYCODE: KeyCode = KeyCode::S; // with ctrl
+const LOAD_KEYCODE: KeyCode = KeyCode::L; // with ctrl
+const HINT_KEYCODE: KeyCode = KeyCode::H;
+const REVIEW_BACK_KEYCODE: KeyCode = KeyCode::Left;
+const REVIEW_FORWARD_KEYCODE: KeyCode = KeyCode::Right;
+const REVIEW_EXIT_KEYCODE: KeyCode = KeyCode::Escape;
+const SCROLL_LINE_HEIGHT: f32 = 20.;
+const REPLAY_TOGGLE_KEYCODE: KeyCode = KeyCode::Space;
+const REPLAY_STEP_KEYCODE: KeyCode = KeyCode::N;
+const REPLAY_SLOWER_KEYCODE: KeyCode = KeyCode::Down;
+const REPLAY_FASTER_KEYCODE: KeyCode = KeyCode::Up;
+const NETWORK_LOST_NOTICE: &str = "The other player disconnected";
+
+pub fn init_game_data(
+    mut commands: Commands,
+    mut game_data: ResMut<resource::GameData>,
+    saved_game: Option<Res<resource::SavedGame>>,
+    replay: Option<ResMut<resource::Replay>>,
+) {
+    if let Some(mut replay) = replay {
+        replay.restart();
+    }
+    // resume a game loaded at startup once, later games start over
+    match saved_game {
+        Some(saved_game) => {
+            **game_data = saved_game.deref().deref().clone();
+            commands.remove_resource::<resource::SavedGame>();
+        }
+        None => game_data.reset(),
+    }
 }
 
 pub fn spawn_board_ui(
     mut commands: Commands,
     board_settings: Res<resource::BoardSettings>,
     game_data: Res<resource::GameData>,
+    replay: Option<Res<resource::Replay>>,
+    asset_server: Res<AssetServer>,
 ) {
     let camera = commands.spawn(Camera2dBundle::default()).id();
     let mut entities = resource::Entities::default();
     entities.push(camera);
     let mut cell_entities = resource::BoardCellEntities::default();
+    let font = asset_server.load::<Font, _>(FONT_PATH);
     let ui = commands
         .spawn(NodeBundle {
             style: Style {
@@ -46,16 +80,20 @@ pub fn spawn_board_ui(
                     style: Style {
                         display: Display::Grid,
                         justify_content: JustifyContent::Center,
-                        aspect_ratio: Some(1.0),
+                        // square cells, the cells are spawned row by row
+                        aspect_ratio: Some(
+                            board_settings.board_size_x().size() as f32
+                                / board_settings.board_size_y().size() as f32,
+                        ),
                         padding: UiRect::all(Val::Percent(1.)),
                         column_gap: Val::Percent(1.),
                         row_gap: Val::Percent(1.),
                         grid_template_columns: RepeatedGridTrack::flex(
-                            board_settings.board_size_y().into(),
+                            board_settings.board_size_x().into(),
                             1.0,
                         ),
                         grid_template_rows: RepeatedGridTrack::flex(
-                            board_settings.board_size_x().into(),
+                            board_settings.board_size_y().into(),
                             1.0,
                         ),
                         ..default()
@@ -68,10 +106,62 @@ pub fn spawn_board_ui(
                         board_settings.board_size_y().into(),
                     ) {
                         let pos = board::BoardPosition { x, y };
-                        let cell_entity = spawn_cell(builder, pos, &board_settings);
+                        if game_data.board().is_blocked(&pos) {
+                            spawn_blocked_cell(builder, &board_settings);
+                            continue;
+                        }
+                        let cell_entity = spawn_cell(builder, pos, &board_settings, font.clone());
                         cell_entities.deref_mut().insert(pos, cell_entity);
                     }
                 });
+
+            // game controls in the corner, out of the board layout
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(10.),
+                        top: Val::Px(10.),
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    if replay.is_some() {
+                        spawn_replay_controls(builder, font.clone(), &board_settings);
+                        return;
+                    }
+                    for (command, label) in [
+                        (data::HistoryCommand::Undo, "Undo"),
+                        (data::HistoryCommand::Redo, "Redo"),
+                    ] {
+                        spawn_control_button(builder, label, font.clone(), &board_settings)
+                            .insert(component::HistoryButton(command));
+                    }
+                });
+
+            // information and the move list on the other side, out of the board layout
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        right: Val::Px(10.),
+                        top: Val::Px(10.),
+                        bottom: Val::Px(10.),
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(10.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_hud(builder, font.clone(), &board_settings);
+                    spawn_move_list(builder, font.clone(), &board_settings);
+                });
         })
         .id();
     entities.push(ui);
@@ -83,11 +173,14 @@ pub fn spawn_cell(
     builder: &mut ChildBuilder,
     pos: board::BoardPosition,
     board_settings: &resource::BoardSettings,
+    font: Handle<Font>,
 ) -> Entity {
     builder
         .spawn(ButtonBundle {
             style: Style {
                 aspect_ratio: Some(1.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
             },
             background_color: board_settings.cell_color_background().into(),
@@ -96,55 +189,194 @@ pub fn spawn_cell(
         .insert(component::Cell)
         .insert(component::BoardPosition(pos))
         .insert(component::Clickable(false))
+        .insert(component::FlipPreview(false))
         .insert(component::Player(data::Player::default()))
+        .with_children(|builder| {
+            builder
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font,
+                        font_size: HINT_FONT_SIZE,
+                        color: board_settings.cell_player_color(&data::Player::Black),
+                    },
+                ))
+                .insert(component::HintText);
+        })
         .id()
 }
 
-pub fn set_initial_player_cells(
-    mut cells: Query<(&component::BoardPosition, &mut component::Player), Added<component::Cell>>,
-    board_settings: Res<resource::BoardSettings>,
-    mut game_data: ResMut<resource::GameData>,
+// A hole keeps its place in the grid, it's no cell so no system touches it.
+fn spawn_blocked_cell(builder: &mut ChildBuilder, board_settings: &resource::BoardSettings) {
+    builder.spawn(NodeBundle {
+        style: Style {
+            aspect_ratio: Some(1.0),
+            ..default()
+        },
+        background_color: board_settings.cell_color_blocked().into(),
+        ..default()
+    });
+}
+
+// Scores, turn and the last move on the other side of the board.
+fn spawn_hud(
+    builder: &mut ChildBuilder,
+    font: Handle<Font>,
+    board_settings: &resource::BoardSettings,
 ) {
-    if cells.is_empty() {
-        return;
+    use component::HudField;
+
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(5.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..default()
+            },
+            background_color: board_settings.cell_color_background().into(),
+            ..default()
+        })
+        .with_children(|builder| {
+            for field in [
+                HudField::Score(data::Player::Black),
+                HudField::Score(data::Player::White),
+                HudField::Turn,
+                HudField::TurnCount,
+                HudField::LastMove,
+                HudField::Notice,
+            ] {
+                let color = match field {
+                    HudField::Score(player) => board_settings.cell_player_color(&player),
+                    _ => board_settings.cell_player_color(&data::Player::Black),
+                };
+                builder
+                    .spawn(TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: CONTROL_FONT_SIZE,
+                            color,
+                        },
+                    ))
+                    .insert(component::HudText(field));
+            }
+        });
+}
+
+// Played moves in notation, clicking one shows the board after it.
+fn spawn_move_list(
+    builder: &mut ChildBuilder,
+    font: Handle<Font>,
+    board_settings: &resource::BoardSettings,
+) {
+    spawn_control_button(builder, "Live", font, board_settings).insert(component::LiveButton);
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                flex_grow: 1.,
+                min_height: Val::Px(0.),
+                overflow: Overflow::clip_y(),
+                ..default()
+            },
+            background_color: board_settings.cell_color_background().into(),
+            ..default()
+        })
+        .with_children(|builder| {
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        align_self: AlignSelf::FlexStart,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(component::MoveList::default());
+        });
+}
+
+// The recorded game is played instead of undone and redone.
+fn spawn_replay_controls(
+    builder: &mut ChildBuilder,
+    font: Handle<Font>,
+    board_settings: &resource::BoardSettings,
+) {
+    for (control, label) in [
+        (replay::Control::TogglePlaying, "Play"),
+        (replay::Control::Step, "Step"),
+        (replay::Control::Slower, "Slower"),
+        (replay::Control::Faster, "Faster"),
+    ] {
+        spawn_control_button(builder, label, font.clone(), board_settings)
+            .insert(component::ReplayButton(control));
     }
+    builder
+        .spawn(TextBundle::from_section(
+            "",
+            TextStyle {
+                font,
+                font_size: CONTROL_FONT_SIZE,
+                color: board_settings.cell_player_color(&data::Player::Black),
+            },
+        ))
+        .insert(component::ReplayStatus);
+}
 
-    const INDEX_OFFSET: u16 = 1;
-    let starting_point = (
-        ((board_settings.board_size_x().size() / 2) - INDEX_OFFSET) as board::PositionUnit,
-        ((board_settings.board_size_y().size() / 2) - INDEX_OFFSET) as board::PositionUnit,
-    );
-    let initial_cell_positions: [(board::BoardPosition, data::Player); 4] = [
-        (
-            (starting_point.0, starting_point.1).into(),
-            data::Player::Black,
-        ),
-        (
-            (starting_point.0 + 1, starting_point.1).into(),
-            data::Player::White,
-        ),
-        (
-            (starting_point.0, starting_point.1 + 1).into(),
-            data::Player::White,
-        ),
-        (
-            (starting_point.0 + 1, starting_point.1 + 1).into(),
-            data::Player::Black,
-        ),
-    ];
-    let initial_cell_positions = initial_cell_positions
-        .into_iter()
-        .collect::<HashMap<board::BoardPosition, data::Player>>();
+pub fn spawn_control_button<'w, 's, 'a>(
+    builder: &'a mut ChildBuilder<'w, 's, '_>,
+    label: &str,
+    font: Handle<Font>,
+    board_settings: &resource::BoardSettings,
+) -> bevy::ecs::system::EntityCommands<'w, 's, 'a> {
+    let mut button = builder.spawn(ButtonBundle {
+        style: Style {
+            padding: UiRect::all(Val::Px(5.)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        background_color: board_settings.cell_color_background().into(),
+        ..default()
+    });
+    button.with_children(|builder| {
+        builder.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font,
+                font_size: CONTROL_FONT_SIZE,
+                color: board_settings.cell_player_color(&data::Player::Black),
+            },
+        ));
+    });
+    button
+}
 
+pub fn set_initial_player_cells(
+    mut cells: Query<(&component::BoardPosition, &mut component::Player), Added<component::Cell>>,
+    game_data: Res<resource::GameData>,
+) {
+    // the initial cells are already on the board, just mirror them
     for (pos, mut player) in cells.iter_mut() {
-        if let Some(set_player) = initial_cell_positions.get(pos.deref()) {
-            **player = *set_player;
+        if let Some(board_player) = game_data.board().cell_ref(pos.deref()) {
+            **player = *board_player;
         }
     }
+}
 
-    for (pos, player) in initial_cell_positions.iter() {
-        let cell_mut = game_data.board_mut().cell_mut(pos.deref()).unwrap();
-        *cell_mut = *player;
+// Not a computer player's turn, nor the turn of the other side of the network.
+fn is_local_human_turn(
+    game_data: &data::GameData,
+    player_controllers: &resource::PlayerControllers,
+    network_peer: Option<&resource::NetworkPeer>,
+) -> bool {
+    if !player_controllers.is_human(game_data.turn()) {
+        return false;
+    }
+    match network_peer {
+        Some(network_peer) => network_peer.is_local_turn(game_data.turn()),
+        None => true,
     }
 }
 
@@ -158,7 +390,14 @@ pub fn button_interaction_system(
         Changed<Interaction>,
     >,
     mut cell_click_event: EventWriter<event::CellClick>,
+    game_data: Res<resource::GameData>,
+    player_controllers: Res<resource::PlayerControllers>,
+    network_peer: Option<Res<resource::NetworkPeer>>,
 ) {
+    if !is_local_human_turn(&game_data, &player_controllers, network_peer.as_deref()) {
+        return;
+    }
+
     for (interaction, board_pos, clickable) in interaction_query.iter_mut() {
         if interaction == &Interaction::Pressed && **clickable {
             cell_click_event.send(event::CellClick(*board_pos.deref()));
@@ -166,132 +405,641 @@ pub fn button_interaction_system(
     }
 }
 
-pub fn change_clicked_player_cell(
-    mut query: Query<(&component::BoardPosition, &mut component::Player)>,
+// Undo and loading only make sense when every move is made here.
+pub fn is_local_game(
+    network_peer: Option<Res<resource::NetworkPeer>>,
+    spectated_game: Option<Res<resource::SpectatedGame>>,
+    replay: Option<Res<resource::Replay>>,
+) -> bool {
+    network_peer.is_none() && spectated_game.is_none() && replay.is_none()
+}
+
+// The moves come from elsewhere and the board is read-only.
+pub fn is_watching(
+    spectated_game: Option<Res<resource::SpectatedGame>>,
+    replay: Option<Res<resource::Replay>>,
+) -> bool {
+    spectated_game.is_some() || replay.is_some()
+}
+
+fn is_ctrl_pressed(key_press: &Input<KeyCode>) -> bool {
+    key_press.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+// Mirror the whole board onto the cells after GameData was rewound or replaced,
+// the clickable cells follow event::TurnChange.
+fn resync_cells(
+    commands: &mut Commands,
+    game_data: &data::GameData,
+    player_cell_changed_writer: &mut EventWriter<event::PlayerCellChanged>,
+    turn_change_writer: &mut EventWriter<event::TurnChange>,
+) {
+    // a computer move being computed belongs to a position that is gone
+    commands.remove_resource::<resource::AiTask>();
+
+    send_board_cells(game_data.board(), player_cell_changed_writer);
+    turn_change_writer.send_default();
+}
+
+fn send_board_cells(
+    board: &data::Board,
+    player_cell_changed_writer: &mut EventWriter<event::PlayerCellChanged>,
+) {
+    player_cell_changed_writer.send_batch(board.cells.iter().map(|(board_position, player)| {
+        event::PlayerCellChanged {
+            player: *player,
+            board_position: *board_position,
+        }
+    }));
+}
+
+pub fn move_list_interaction(
+    mut commands: Commands,
+    entries: Query<(&Interaction, &component::MoveListEntry), Changed<Interaction>>,
+    live_buttons: Query<&Interaction, (Changed<Interaction>, With<component::LiveButton>)>,
+) {
+    for (interaction, entry) in entries.iter() {
+        if interaction == &Interaction::Pressed {
+            commands.insert_resource(resource::Review(**entry + 1));
+        }
+    }
+    if live_buttons
+        .iter()
+        .any(|interaction| interaction == &Interaction::Pressed)
+    {
+        commands.remove_resource::<resource::Review>();
+    }
+}
+
+// Steps back from the live game into the review and through it, stepping past the end leaves it.
+pub fn review_keyboard_input(
+    mut commands: Commands,
+    key_press: Res<Input<KeyCode>>,
+    review: Option<Res<resource::Review>>,
+    game_data: Res<resource::GameData>,
+) {
+    let moves = game_data.history().len();
+    let reviewed = review.map(|review| **review);
+    let count = if key_press.just_pressed(REVIEW_BACK_KEYCODE) {
+        Some(reviewed.unwrap_or(moves).saturating_sub(1))
+    } else if key_press.just_pressed(REVIEW_FORWARD_KEYCODE) {
+        reviewed.map(|count| count + 1)
+    } else if key_press.just_pressed(REVIEW_EXIT_KEYCODE) {
+        reviewed.map(|_| moves)
+    } else {
+        None
+    };
+    match count {
+        Some(count) if count < moves => commands.insert_resource(resource::Review(count)),
+        Some(_) => commands.remove_resource::<resource::Review>(),
+        None => (),
+    }
+}
+
+// The reviewed position replaces the live one on the cells, which can't be clicked meanwhile.
+pub fn show_review(
+    mut commands: Commands,
+    review: Option<Res<resource::Review>>,
+    game_data: Res<resource::GameData>,
+    mut cells: Query<&mut component::Clickable>,
+    mut player_cell_changed_writer: EventWriter<event::PlayerCellChanged>,
+    mut turn_change_writer: EventWriter<event::TurnChange>,
+) {
+    match review {
+        Some(review) => {
+            let reviewed = game_data.after_moves(**review);
+            send_board_cells(reviewed.board(), &mut player_cell_changed_writer);
+            for mut clickable in cells.iter_mut() {
+                clickable.set_if_neq(component::Clickable(false));
+            }
+        }
+        None => resync_cells(
+            &mut commands,
+            &game_data,
+            &mut player_cell_changed_writer,
+            &mut turn_change_writer,
+        ),
+    }
+}
+
+pub fn update_move_list(
+    mut commands: Commands,
+    lists: Query<Entity, With<component::MoveList>>,
+    game_data: Res<resource::GameData>,
+    review: Option<Res<resource::Review>>,
+    board_settings: Res<resource::BoardSettings>,
+    asset_server: Res<AssetServer>,
+) {
+    let font = asset_server.load::<Font, _>(FONT_PATH);
+    let reviewed = review.map(|review| **review);
+    for list in lists.iter() {
+        commands
+            .entity(list)
+            .despawn_descendants()
+            .with_children(|builder| {
+                for (index, m) in game_data.history().iter().enumerate() {
+                    let label = format!("{}. {}", index + 1, notation::move_to_string(m));
+                    let mut entry =
+                        spawn_control_button(builder, &label, font.clone(), &board_settings);
+                    entry.insert(component::MoveListEntry(index));
+                    if reviewed == Some(index + 1) {
+                        entry.insert(BackgroundColor(board_settings.cell_color_clickable()));
+                    }
+                }
+            });
+    }
+}
+
+pub fn scroll_move_list(
+    mut mouse_wheel_reader: EventReader<bevy::input::mouse::MouseWheel>,
+    mut lists: Query<(&mut component::MoveList, &mut Style, &Parent, &Node)>,
+    nodes: Query<&Node>,
+) {
+    use bevy::input::mouse::MouseScrollUnit;
+
+    for mouse_wheel in mouse_wheel_reader.iter() {
+        let dy = match mouse_wheel.unit {
+            MouseScrollUnit::Line => mouse_wheel.y * SCROLL_LINE_HEIGHT,
+            MouseScrollUnit::Pixel => mouse_wheel.y,
+        };
+        for (mut list, mut style, parent, list_node) in lists.iter_mut() {
+            let panel_height = nodes
+                .get(parent.get())
+                .map(|node| node.size().y)
+                .unwrap_or_default();
+            let max_scroll = (list_node.size().y - panel_height).max(0.);
+            list.scroll = (list.scroll + dy).clamp(-max_scroll, 0.);
+            style.top = Val::Px(list.scroll);
+        }
+    }
+}
+
+pub fn history_keyboard_input(
+    key_press: Res<Input<KeyCode>>,
+    mut history_request_writer: EventWriter<event::HistoryRequest>,
+) {
+    if !is_ctrl_pressed(&key_press) {
+        return;
+    }
+    let shift = key_press.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if key_press.just_pressed(UNDO_KEYCODE) && !shift {
+        history_request_writer.send(event::HistoryRequest(data::HistoryCommand::Undo));
+    } else if key_press.just_pressed(UNDO_KEYCODE) || key_press.just_pressed(REDO_KEYCODE) {
+        history_request_writer.send(event::HistoryRequest(data::HistoryCommand::Redo));
+    }
+}
+
+pub fn history_button_interaction(
+    query: Query<(&Interaction, &component::HistoryButton), Changed<Interaction>>,
+    mut history_request_writer: EventWriter<event::HistoryRequest>,
+) {
+    for (interaction, history_button) in query.iter() {
+        if interaction == &Interaction::Pressed {
+            history_request_writer.send(event::HistoryRequest(**history_button));
+        }
+    }
+}
+
+pub fn apply_history_request(
+    mut commands: Commands,
     mut game_data: ResMut<resource::GameData>,
-    mut cell_click_reader: EventReader<event::CellClick>,
+    player_controllers: Res<resource::PlayerControllers>,
+    mut history_request_reader: EventReader<event::HistoryRequest>,
     mut player_cell_changed_writer: EventWriter<event::PlayerCellChanged>,
+    mut turn_change_writer: EventWriter<event::TurnChange>,
 ) {
-    if let Some(cell_click) = cell_click_reader.iter().next() {
-        // find the entity and change the player component
-        let (board_position, mut matched_cell_player) = query
-            .iter_mut()
-            .find(|(pos, _)| pos.eq(cell_click))
-            .unwrap();
-        let new_player = game_data.current_player();
-        **matched_cell_player = new_player;
+    let Some(command) = history_request_reader.iter().next() else {
+        return;
+    };
 
-        // update player on the game data
-        let board_player_mut = game_data.board_mut().cell_mut(board_position).unwrap();
-        *board_player_mut = new_player;
+    if !ai::apply_history_command(&mut game_data, &player_controllers, **command) {
+        return;
+    }
+    info!("{:?} to turn {}", **command, game_data.turn_count());
+    resync_cells(
+        &mut commands,
+        &game_data,
+        &mut player_cell_changed_writer,
+        &mut turn_change_writer,
+    );
+}
 
-        player_cell_changed_writer.send(event::PlayerCellChanged {
-            player: new_player,
-            board_position: **board_position,
-        })
+pub fn save_game_input(key_press: Res<Input<KeyCode>>, game_data: Res<resource::GameData>) {
+    if !is_ctrl_pressed(&key_press) || !key_press.just_pressed(SAVE_KEYCODE) {
+        return;
+    }
+    match save::save(save::DEFAULT_SAVE_PATH, &game_data) {
+        Ok(()) => info!("game saved to {}", save::DEFAULT_SAVE_PATH),
+        Err(e) => warn!("Cannot save the game: {}", e),
     }
 }
 
-pub fn change_opposite_player_cells(
-    mut query: Query<(&component::BoardPosition, &mut component::Player)>,
+pub fn load_game_input(
+    mut commands: Commands,
+    key_press: Res<Input<KeyCode>>,
     mut game_data: ResMut<resource::GameData>,
-    mut player_cell_changed_reader: EventReader<event::PlayerCellChanged>,
+    board_settings: Res<resource::BoardSettings>,
+    mut player_cell_changed_writer: EventWriter<event::PlayerCellChanged>,
+    mut turn_change_writer: EventWriter<event::TurnChange>,
 ) {
-    if let Some(player_cell_changed) = player_cell_changed_reader.iter().next() {
-        let current_player = game_data.current_player();
-        let opposite_player = game_data.opposite_player();
+    if !is_ctrl_pressed(&key_press) || !key_press.just_pressed(LOAD_KEYCODE) {
+        return;
+    }
+    let loaded = save::load(save::DEFAULT_SAVE_PATH).and_then(|loaded| {
+        save::check_board(&loaded, &board_settings)?;
+        Ok(loaded)
+    });
+    match loaded {
+        Ok(loaded) => {
+            **game_data = loaded;
+            info!("game loaded from {}", save::DEFAULT_SAVE_PATH);
+            resync_cells(
+                &mut commands,
+                &game_data,
+                &mut player_cell_changed_writer,
+                &mut turn_change_writer,
+            );
+        }
+        Err(e) => warn!("Cannot load the game: {}", e),
+    }
+}
 
-        // try to get opposite player cell's position that connects with current player in all directions
-        let opposite_positions = board::DIRECTIONS
-            .iter()
-            .flat_map(|direction| {
-                let mut iter = board::Iter::new(
-                    game_data.board(),
-                    player_cell_changed.board_position,
-                    *direction,
-                    1,
-                );
-
-                let mut opposite_positions: Vec<board::BoardPosition> = vec![];
-                // todo: implement function for iterator. somthing like take_until...?
-                let found_same_player_on_other_side = loop {
-                    match iter.next() {
-                        Some((pos, player)) if player.eq(&opposite_player) => {
-                            opposite_positions.push(pos);
-                        }
-                        Some((_, player)) if player.eq(&current_player) => {
-                            break true;
+pub fn start_ai_turn(
+    mut commands: Commands,
+    game_data: Res<resource::GameData>,
+    player_controllers: Res<resource::PlayerControllers>,
+) {
+    let Some(player_controller) = player_controllers.get(game_data.turn()) else {
+        return;
+    };
+    // passing is left to event::TurnStuck
+    if player_controller.is_human() || game_data.legal_moves().is_empty() {
+        return;
+    }
+
+    let player_controller = player_controller.clone();
+    let game_data = game_data.deref().deref().clone();
+    let turn_count = game_data.turn_count();
+    let handle = std::thread::spawn(move || player_controller.choose_move(&game_data));
+    commands.insert_resource(resource::AiTask {
+        turn_count,
+        handle: Some(handle),
+    });
+}
+
+pub fn finish_ai_turn(
+    mut commands: Commands,
+    mut ai_task: ResMut<resource::AiTask>,
+    game_data: Res<resource::GameData>,
+    mut cell_click_event: EventWriter<event::CellClick>,
+) {
+    if let Some(handle) = &ai_task.handle {
+        if !handle.is_finished() {
+            return;
+        }
+    }
+
+    commands.remove_resource::<resource::AiTask>();
+    let Some(handle) = ai_task.handle.take() else {
+        return;
+    };
+    match handle.join() {
+        // drop moves computed for a position that is gone
+        Ok(Some(pos)) if ai_task.turn_count == game_data.turn_count() => {
+            cell_click_event.send(event::CellClick(pos));
+        }
+        Ok(_) => (),
+        Err(_) => error!("AI thread panicked"),
+    }
+}
+
+pub fn change_clicked_player_cell(
+    mut game_data: ResMut<resource::GameData>,
+    mut cell_click_reader: EventReader<event::CellClick>,
+    mut player_cell_changed_writer: EventWriter<event::PlayerCellChanged>,
+    mut turn_change_writer: EventWriter<event::TurnChange>,
+    mut discs_flipped_writer: EventWriter<event::DiscsFlipped>,
+    network_peer: Option<ResMut<resource::NetworkPeer>>,
+) {
+    if let Some(cell_click) = cell_click_reader.iter().next() {
+        let player = game_data.current_player();
+        let turn = *game_data.turn();
+        match game_data.play(cell_click) {
+            Ok(flipped) => {
+                if let Some(mut network_peer) = network_peer {
+                    if network_peer.is_local_turn(&turn) {
+                        let message = network::Message::CellClick(**cell_click);
+                        if let Err(e) = network_peer.send(&message) {
+                            error!("Cannot send move: {}", e);
                         }
-                        _ => break false,
                     }
-                };
+                }
 
-                if found_same_player_on_other_side {
-                    opposite_positions
-                } else {
-                    vec![]
+                let changed_positions = std::iter::once(**cell_click).chain(flipped.clone());
+                discs_flipped_writer.send(event::DiscsFlipped {
+                    player,
+                    placed: **cell_click,
+                    flipped,
+                });
+                player_cell_changed_writer.send_batch(changed_positions.map(|board_position| {
+                    event::PlayerCellChanged {
+                        player,
+                        board_position,
+                    }
+                }));
+                turn_change_writer.send_default();
+            }
+            Err(e) => warn!("Cannot play at {:?}: {:?}", **cell_click, e),
+        }
+    }
+}
+
+// Moves of the other side become event::CellClick, the host plays them only when they are valid.
+pub fn receive_network_move(
+    mut commands: Commands,
+    mut network_peer: ResMut<resource::NetworkPeer>,
+    mut game_data: ResMut<resource::GameData>,
+    mut cell_click_writer: EventWriter<event::CellClick>,
+    mut player_cell_changed_writer: EventWriter<event::PlayerCellChanged>,
+    mut turn_change_writer: EventWriter<event::TurnChange>,
+) {
+    // one move per frame, the next one depends on it
+    let position = match network_peer.receive() {
+        Ok(Some(network::Message::CellClick(position))) => position,
+        Ok(Some(network::Message::Rejected { reason, moves })) => {
+            // the move was played here before the host checked it, go back to the host's game
+            error!("The host rejected the move: {}", reason);
+            **game_data = game_data.after_moves(moves);
+            resync_cells(
+                &mut commands,
+                &game_data,
+                &mut player_cell_changed_writer,
+                &mut turn_change_writer,
+            );
+            return;
+        }
+        Ok(Some(message)) => {
+            warn!("Ignored network message {:?}", message);
+            return;
+        }
+        Ok(None) => return,
+        // the line is dropped, the next one may be fine
+        Err(e @ network::NetworkError::Read(_)) => {
+            warn!("Ignored network message: {}", e);
+            return;
+        }
+        Err(e) => {
+            // neither side can go on alone
+            error!("{}, the game is stopped", e);
+            commands.remove_resource::<resource::NetworkPeer>();
+            commands.insert_resource(resource::NetworkLost);
+            return;
+        }
+    };
+
+    match network_peer.check_remote_move(&game_data, &position) {
+        Ok(()) => cell_click_writer.send(event::CellClick(position)),
+        Err(e) => {
+            warn!("Rejected move at {:?}: {}", position, e);
+            if network_peer.role() == network::Role::Host {
+                let message = network::Message::Rejected {
+                    reason: e.to_string(),
+                    moves: game_data.history().len(),
+                };
+                if let Err(e) = network_peer.send(&message) {
+                    error!("Cannot send rejection: {}", e);
                 }
-            })
-            .map(|pos| (pos, ()))
-            .collect::<HashMap<board::BoardPosition, ()>>();
+            }
+        }
+    }
+}
 
-        // update entities
-        for (board_position, mut player) in query.iter_mut() {
-            if opposite_positions.contains_key(board_position.deref()) {
-                **player = current_player;
+// Mirrors the spectated game, its moves are played through event::CellClick.
+#[allow(clippy::too_many_arguments)]
+pub fn receive_spectator_message(
+    mut commands: Commands,
+    mut spectated_game: ResMut<resource::SpectatedGame>,
+    mut game_data: ResMut<resource::GameData>,
+    board_settings: Res<resource::BoardSettings>,
+    mut cell_click_writer: EventWriter<event::CellClick>,
+    mut player_cell_changed_writer: EventWriter<event::PlayerCellChanged>,
+    mut turn_change_writer: EventWriter<event::TurnChange>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut result_event_writer: EventWriter<result::event::ResultEvent>,
+) {
+    // one message per frame, a move has to be played before the next one
+    let message = match spectated_game.receive() {
+        Ok(Some(message)) => message,
+        Ok(None) => return,
+        Err(e) => {
+            error!("The spectated game is gone: {}", e);
+            return;
+        }
+    };
+
+    match message {
+        spectator::SpectatorMessage::Snapshot(snapshot) => {
+            let checked = save::check_board(&snapshot, &board_settings)
+                .and_then(|_| save::check_history(&snapshot));
+            if let Err(e) = checked {
+                error!("Cannot show the spectated game: {}", e);
+                return;
             }
+            **game_data = snapshot;
+            resync_cells(
+                &mut commands,
+                &game_data,
+                &mut player_cell_changed_writer,
+                &mut turn_change_writer,
+            );
         }
+        spectator::SpectatorMessage::Move(data::Move::Place(position)) => {
+            cell_click_writer.send(event::CellClick(position))
+        }
+        spectator::SpectatorMessage::Move(data::Move::Pass) => match game_data.pass() {
+            Ok(()) => turn_change_writer.send_default(),
+            Err(e) => warn!("Cannot pass the turn: {:?}", e),
+        },
+        spectator::SpectatorMessage::Result(result_data) => {
+            next_game_state.set(GameState::Result);
+            result_event_writer.send(result::event::ResultEvent(result_data));
+        }
+    }
+}
+
+pub fn replay_input(
+    key_press: Res<Input<KeyCode>>,
+    query: Query<(&Interaction, &component::ReplayButton), Changed<Interaction>>,
+    mut replay: ResMut<resource::Replay>,
+) {
+    let buttons = query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, replay_button)| **replay_button);
+    let keys = [
+        (REPLAY_TOGGLE_KEYCODE, replay::Control::TogglePlaying),
+        (REPLAY_STEP_KEYCODE, replay::Control::Step),
+        (REPLAY_SLOWER_KEYCODE, replay::Control::Slower),
+        (REPLAY_FASTER_KEYCODE, replay::Control::Faster),
+    ]
+    .into_iter()
+    .filter(|(keycode, _)| key_press.just_pressed(*keycode))
+    .map(|(_, control)| control);
+    for control in buttons.chain(keys).collect::<Vec<_>>() {
+        replay.apply(control);
+    }
+}
+
+// Plays the recorded moves like the live game, a placed disc goes through event::CellClick.
+pub fn advance_replay(
+    time: Res<Time>,
+    mut replay: ResMut<resource::Replay>,
+    mut game_data: ResMut<resource::GameData>,
+    mut cell_click_writer: EventWriter<event::CellClick>,
+    mut turn_change_writer: EventWriter<event::TurnChange>,
+) {
+    match replay.next_move(time.delta()) {
+        Some(data::Move::Place(position)) => cell_click_writer.send(event::CellClick(position)),
+        Some(data::Move::Pass) => match game_data.pass() {
+            Ok(()) => turn_change_writer.send_default(),
+            Err(e) => warn!("Cannot pass the turn: {:?}", e),
+        },
+        None => (),
+    }
+}
+
+pub fn update_replay_status(
+    replay: Res<resource::Replay>,
+    mut query: Query<&mut Text, With<component::ReplayStatus>>,
+) {
+    let state = if replay.is_playing() {
+        "Playing"
+    } else {
+        "Paused"
+    };
+    let status = format!(
+        "{} {}x\n{}/{}",
+        state,
+        replay.speed(),
+        replay.position(),
+        replay.len()
+    );
+    for mut text in query.iter_mut() {
+        text.sections[0].value = status.clone();
+    }
+}
 
-        // update board in game_data
-        for position in opposite_positions.keys() {
-            let cell_mut = game_data.board_mut().cell_mut(position).unwrap();
-            *cell_mut = current_player;
+pub fn broadcast_game(
+    mut broadcaster: ResMut<resource::SpectatorBroadcaster>,
+    game_data: Res<resource::GameData>,
+    mut result_event_reader: EventReader<result::event::ResultEvent>,
+) {
+    if let Err(e) = broadcaster.update(&game_data) {
+        error!("Cannot broadcast the game: {}", e);
+    }
+    for result_event in result_event_reader.iter() {
+        let message = spectator::SpectatorMessage::Result(result_event.deref().clone());
+        if let Err(e) = broadcaster.broadcast(&message) {
+            error!("Cannot broadcast the result: {}", e);
         }
     }
 }
 
-pub fn clear_cell_clickable(mut cells: Query<&mut component::Clickable>) {
-    for mut clickable in cells.iter_mut() {
-        **clickable = false;
+pub fn change_player_cells(
+    mut query: Query<&mut component::Player>,
+    board_entities: Res<resource::BoardCellEntities>,
+    mut player_cell_changed_reader: EventReader<event::PlayerCellChanged>,
+) {
+    for player_cell_changed in player_cell_changed_reader.iter() {
+        let entity = board_entities
+            .get(&player_cell_changed.board_position)
+            .unwrap();
+        if let Ok(mut player) = query.get_mut(*entity) {
+            **player = player_cell_changed.player;
+        }
     }
 }
 
-pub fn update_cell_clickable(
+pub fn start_flip_animations(
     mut commands: Commands,
-    mut cells: Query<(&component::BoardPosition, &component::Player)>,
-    game_data: Res<resource::GameData>,
     board_entities: Res<resource::BoardCellEntities>,
+    board_settings: Res<resource::BoardSettings>,
+    mut discs_flipped_reader: EventReader<event::DiscsFlipped>,
 ) {
-    let game_data = &**game_data;
-    let current_player = game_data.current_player();
-    let opposite_player = game_data.opposite_player();
-    for (board_position, player) in cells.iter_mut() {
-        // check only if the cell matches the current turn player
-        if player.ne(&current_player) {
+    for discs_flipped in discs_flipped_reader.iter() {
+        let from = board_settings.cell_player_color(&discs_flipped.player.next());
+        for position in discs_flipped.flipped.iter() {
+            // the flips run along lines, the distance is the number of steps on the line
+            let distance = (position.x - discs_flipped.placed.x)
+                .abs()
+                .max((position.y - discs_flipped.placed.y).abs());
+            let delay = board_settings.flip_animation_stagger() * distance as u32;
+            commands
+                .entity(board_entities[position])
+                .insert(component::FlipAnimation {
+                    from,
+                    delay: Timer::new(delay, TimerMode::Once),
+                    timer: Timer::new(board_settings.flip_animation_duration(), TimerMode::Once),
+                });
+        }
+    }
+}
+
+pub fn animate_flips(
+    mut commands: Commands,
+    mut cells: Query<(
+        Entity,
+        &mut BackgroundColor,
+        &component::Player,
+        &mut component::FlipAnimation,
+    )>,
+    board_settings: Res<resource::BoardSettings>,
+    time: Res<Time>,
+) {
+    for (entity, mut background_color, player, mut animation) in cells.iter_mut() {
+        let animation = animation.as_mut();
+        if !animation.delay.tick(time.delta()).finished() {
+            *background_color = animation.from.into();
             continue;
         }
+        animation.timer.tick(time.delta());
+        let to = board_settings.cell_player_color(player);
+        *background_color = mix_colors(animation.from, to, animation.timer.percent()).into();
+        if animation.timer.finished() {
+            commands.entity(entity).remove::<component::FlipAnimation>();
+        }
+    }
+}
 
-        for direction in board::DIRECTIONS.iter() {
-            let mut iter =
-                board::Iter::new(game_data.board(), *board_position.deref(), *direction, 1);
+pub fn is_animating(animations: Query<(), With<component::FlipAnimation>>) -> bool {
+    !animations.is_empty()
+}
 
-            // the next cell has to be the opposite player, skip loop if not
-            // "if let &&" pattern is not available here
-            match iter.next() {
-                Some((_, player)) if player.ne(&opposite_player) => {
-                    continue;
-                }
-                _ => (),
-            }
+// Input waits until the flipped discs show their new color, and while an earlier position is reviewed.
+// A network game stays locked once the other player is gone.
+pub fn is_board_locked(
+    animations: Query<(), With<component::FlipAnimation>>,
+    review: Option<Res<resource::Review>>,
+    network_lost: Option<Res<resource::NetworkLost>>,
+) -> bool {
+    !animations.is_empty() || review.is_some() || network_lost.is_some()
+}
 
-            // skip all opposite players
-            let mut iter = iter.skip_while(|(_, &p)| p.eq(&opposite_player));
+pub fn clear_cell_clickable(mut cells: Query<&mut component::Clickable>) {
+    for mut clickable in cells.iter_mut() {
+        **clickable = false;
+    }
+}
 
-            // check if the cell has empty player
-            if let Some((cell_position, data::Player::None)) = iter.next() {
-                let entity = board_entities.get(&cell_position).unwrap();
-                commands.entity(*entity).insert(component::Clickable(true)); // todo: check if there's any way to change the component instead of insert
-            }
+pub fn update_cell_clickable(
+    mut cells: Query<&mut component::Clickable>,
+    game_data: Res<resource::GameData>,
+    board_entities: Res<resource::BoardCellEntities>,
+) {
+    for position in game_data.legal_moves() {
+        let entity = board_entities.get(&position).unwrap();
+        if let Ok(mut clickable) = cells.get_mut(*entity) {
+            **clickable = true;
         }
     }
 }
@@ -301,24 +1049,50 @@ pub fn change_cell_color(
     mut cells: Query<
         (
             &mut BackgroundColor,
+            &component::BoardPosition,
             &component::Player,
             &component::Clickable,
+            &component::FlipPreview,
+            Option<&component::FlipAnimation>,
         ),
         (
             With<component::Cell>,
             Or<(
                 Changed<component::Clickable>,
                 Added<component::Clickable>,
+                Changed<component::FlipPreview>,
                 Changed<component::Player>,
                 Added<component::Player>,
             )>,
         ),
     >,
     board_settings: Res<resource::BoardSettings>,
+    hints: Option<Res<resource::Hints>>,
 ) {
-    for (mut background_color, player, clickable) in cells.iter_mut() {
+    for (mut background_color, board_pos, player, clickable, flip_preview, flip_animation) in
+        cells.iter_mut()
+    {
+        // animate_flips colors the disc
+        if flip_animation.is_some() {
+            continue;
+        }
+        if **flip_preview {
+            *background_color = board_settings.cell_color_preview().into();
+            continue;
+        }
+
         if **clickable {
-            *background_color = board_settings.cell_color_clickable().into();
+            // the better the move, the closer to the hint color
+            let rank = hints.as_ref().and_then(|hints| hints.rank(board_pos));
+            *background_color = match rank {
+                Some(rank) => mix_colors(
+                    board_settings.cell_color_clickable(),
+                    board_settings.cell_color_hint(),
+                    rank,
+                ),
+                None => board_settings.cell_color_clickable(),
+            }
+            .into();
             continue;
         }
 
@@ -326,20 +1100,172 @@ pub fn change_cell_color(
     }
 }
 
-pub fn update_turn(
-    mut game_data: ResMut<resource::GameData>,
-    mut turn_stuck_reader: EventReader<event::TurnStuck>,
+// Tints the discs a hovered move would flip, until the cursor leaves or the turn changes.
+#[allow(clippy::type_complexity)]
+pub fn preview_flips(
+    interaction_query: Query<
+        (
+            &Interaction,
+            &component::BoardPosition,
+            &component::Clickable,
+        ),
+        Changed<Interaction>,
+    >,
+    mut cells: Query<(&component::BoardPosition, &mut component::FlipPreview)>,
+    game_data: Res<resource::GameData>,
+    player_controllers: Res<resource::PlayerControllers>,
+    network_peer: Option<Res<resource::NetworkPeer>>,
+    mut turn_change_reader: EventReader<event::TurnChange>,
+) {
+    let turn_changed = turn_change_reader.iter().count() > 0;
+    let mut hovered = None;
+    let mut left = turn_changed;
+    for (interaction, board_pos, clickable) in interaction_query.iter() {
+        match interaction {
+            Interaction::Hovered if **clickable => hovered = Some(*board_pos.deref()),
+            _ => left = true,
+        }
+    }
+    if hovered.is_none() && !left {
+        return;
+    }
+
+    let flips = match hovered {
+        Some(position)
+            if is_local_human_turn(&game_data, &player_controllers, network_peer.as_deref()) =>
+        {
+            rules::flips(game_data.board(), &position, game_data.current_player())
+        }
+        _ => vec![],
+    };
+    for (board_pos, mut flip_preview) in cells.iter_mut() {
+        flip_preview.set_if_neq(component::FlipPreview(flips.contains(board_pos.deref())));
+    }
+}
+
+fn mix_colors(from: Color, to: Color, ratio: f32) -> Color {
+    let [from_r, from_g, from_b, from_a] = from.as_rgba_f32();
+    let [to_r, to_g, to_b, to_a] = to.as_rgba_f32();
+    let mix = |from: f32, to: f32| from + (to - from) * ratio;
+    Color::rgba(
+        mix(from_r, to_r),
+        mix(from_g, to_g),
+        mix(from_b, to_b),
+        mix(from_a, to_a),
+    )
+}
+
+pub fn toggle_hints(
+    mut commands: Commands,
+    key_press: Res<Input<KeyCode>>,
+    hints: Option<Res<resource::Hints>>,
+    game_data: Res<resource::GameData>,
+    hint_settings: Res<resource::HintSettings>,
+    mut cells: Query<&mut component::Clickable>,
+) {
+    if !key_press.just_pressed(HINT_KEYCODE) {
+        return;
+    }
+
+    match hints {
+        Some(_) => commands.remove_resource::<resource::Hints>(),
+        None => commands.insert_resource(compute_hints(&game_data, &hint_settings)),
+    }
+    for mut clickable in cells.iter_mut() {
+        clickable.set_changed();
+    }
+}
+
+fn compute_hints(game_data: &data::GameData, hint_settings: &ai::HintSettings) -> resource::Hints {
+    let mut hints = resource::Hints::default();
+    hints.extend(ai::hint_scores(game_data, hint_settings));
+    hints
+}
+
+// Runs with the clickable cells, which then get the hint colors.
+pub fn update_hints(
+    mut hints: ResMut<resource::Hints>,
+    game_data: Res<resource::GameData>,
+    hint_settings: Res<resource::HintSettings>,
+) {
+    *hints = compute_hints(&game_data, &hint_settings);
+}
+
+pub fn change_hint_text(
+    mut texts: Query<(&mut Text, &Parent), With<component::HintText>>,
+    cells: Query<&component::BoardPosition>,
+    hints: Option<Res<resource::Hints>>,
 ) {
-    let is_turn_stuck = turn_stuck_reader.iter().next().is_some();
-    if is_turn_stuck {
-        game_data.notify_turn_stuck();
+    let best = hints
+        .as_ref()
+        .and_then(|hints| hints.values().max().copied());
+    for (mut text, parent) in texts.iter_mut() {
+        let score = cells
+            .get(parent.get())
+            .ok()
+            .and_then(|board_pos| hints.as_ref()?.get(board_pos.deref()).copied());
+        text.sections[0].value = match score {
+            // the best move is starred
+            Some(score) if Some(score) == best => format!("*{}", score),
+            Some(score) => score.to_string(),
+            None => String::new(),
+        };
     }
+}
 
-    game_data.next_turn();
+fn hud_field_text(field: component::HudField, game_data: &data::GameData) -> String {
+    use component::HudField;
+
+    match field {
+        HudField::Score(player) => {
+            format!("{:?}: {}", player, game_data.score().get(&player))
+        }
+        HudField::Turn if game_data.is_game_over() => "Game over".to_string(),
+        HudField::Turn => format!("{:?} to move", game_data.turn()),
+        HudField::TurnCount => format!("Turn {}", game_data.turn_count()),
+        HudField::LastMove => game_data
+            .history()
+            .iter()
+            .rev()
+            .find_map(|m| match m {
+                data::Move::Place(position) => {
+                    Some(format!("Last move: {}", notation::to_string(position)))
+                }
+                data::Move::Pass => None,
+            })
+            .unwrap_or_default(),
+        // a pass is the last move until the other player moves
+        HudField::Notice => match game_data.history().last() {
+            Some(data::Move::Pass) => format!("{:?} had to pass", game_data.turn().next()),
+            _ => String::new(),
+        },
+    }
+}
+
+pub fn update_hud(
+    mut texts: Query<(&mut Text, &component::HudText)>,
+    game_data: Res<resource::GameData>,
+    network_lost: Option<Res<resource::NetworkLost>>,
+) {
+    for (mut text, hud_text) in texts.iter_mut() {
+        text.sections[0].value = match **hud_text {
+            component::HudField::Notice if network_lost.is_some() => {
+                NETWORK_LOST_NOTICE.to_string()
+            }
+            field => hud_field_text(field, &game_data),
+        };
+    }
+}
+
+pub fn pass_turn(mut game_data: ResMut<resource::GameData>) {
+    if let Err(e) = game_data.pass() {
+        warn!("Cannot pass the turn: {:?}", e);
+        return;
+    }
 
     {
         let turn = game_data.turn();
-        info!("update_turn to player({:?})", turn);
+        info!("pass_turn to player({:?})", turn);
     }
 }
 
@@ -348,52 +1274,37 @@ pub fn any_clickable_cell(query: Query<&component::Clickable>) -> bool {
 }
 
 pub fn check_win_condition(
-    query: Query<&component::Player, With<component::Cell>>,
     game_data: Res<resource::GameData>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut result_event_writer: EventWriter<result::event::ResultEvent>,
 ) {
-    if game_data.is_turn_stuck() {
+    if game_data.is_game_over() {
         info!("The game has ended, there's no clickable cell for both players.");
+        info!("transcript : {}", game_data.transcript());
+        if let Some(handicap) = game_data.handicap() {
+            info!("handicap : {}", handicap);
+        }
 
         // go to the result game state
         next_game_state.set(GameState::Result);
 
         // calculate total score and send through the event
-        let cell_count_map: HashMap<data::Player, usize> = query
-            .iter()
-            .map(|x| x.deref())
-            .cloned()
-            .sorted_by(|a, b| a.cmp(b))
-            .group_by(|x| *x)
-            .into_iter()
-            .map(|(player, group)| (player, group.count()))
-            .collect();
-        info!("cell_count_map : {:?}", &cell_count_map);
-
+        let score = game_data.score();
         let result_score = [
-            (
-                result::data::PlayerType::White,
-                cell_count_map
-                    .get(&data::Player::White)
-                    .cloned()
-                    .unwrap_or_default() as u16,
-            ),
-            (
-                result::data::PlayerType::Black,
-                cell_count_map
-                    .get(&data::Player::Black)
-                    .cloned()
-                    .unwrap_or_default() as u16,
-            ),
+            (result::data::PlayerType::White, score.white),
+            (result::data::PlayerType::Black, score.black),
         ]
         .into_iter()
         .collect();
         info!("result_score : {:?}", &result_score);
 
-        let result_event = result::event::ResultEvent(result::data::ResultData {
+        let result_data = result::data::ResultData {
             scores: result_score,
-        });
+            rule_set: game_data.rule_set(),
+        };
+        info!("result : {}", result_data.summary());
+
+        let result_event = result::event::ResultEvent(result_data);
 
         result_event_writer.send(result_event);
     }
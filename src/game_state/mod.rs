@@ -14,7 +14,10 @@ use super::board;
 
 pub mod plugin {
     pub use super::{
-        game::{data::BoardSettings, plugin::GamePlugin},
+        game::{
+            data::BoardSettings,
+            plugin::{Connection, GamePlugin, NetworkPlugin},
+        },
         result::plugin::ResultPlugin,
         GameStatePlugin,
     };
@@ -22,11 +25,39 @@ pub mod plugin {
 
 pub mod data {
     pub use super::{
-        game::data::{Player, Turn},
+        game::data::{BoardSize, GameData, HistoryCommand, Player, Turn},
         result::data::{PlayerType as ResultPlayer, ResultData, Settings},
     };
 }
 
+pub mod ai {
+    pub use super::game::ai::*;
+}
+
+pub mod rules {
+    pub use super::game::rules::*;
+}
+
+pub mod notation {
+    pub use super::game::notation::*;
+}
+
+pub mod network {
+    pub use super::game::{network::*, spectator::*};
+}
+
+pub mod replay {
+    pub use super::game::replay::*;
+}
+
+pub mod layout {
+    pub use super::game::layout::*;
+}
+
+pub mod save {
+    pub use super::game::save::*;
+}
+
 pub mod event {
     pub use super::result::event::ResultEvent;
 }
@@ -66,7 +66,10 @@ pub mod data {
     use bevy::{prelude::Color, utils::HashMap};
     use serde::{Deserialize, Serialize};
 
+    use crate::game_state::game::{data::Player, rules};
+
     pub(super) const FONT_SIZE: f32 = 100.;
+    pub(super) const HEADER_FONT_SIZE: f32 = 40.;
 
     #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
     pub enum PlayerType {
@@ -86,9 +89,35 @@ pub mod data {
 
     pub type CellCount = u16;
 
-    #[derive(Clone, Debug)]
+    #[derive(Clone, Debug, Serialize, Deserialize)]
     pub struct ResultData {
         pub scores: HashMap<PlayerType, CellCount>,
+        #[serde(default)]
+        pub rule_set: rules::RuleSet, // decides whether more or fewer discs win
+    }
+
+    impl ResultData {
+        // None means a draw.
+        pub fn winner(&self) -> Option<PlayerType> {
+            let score = |player_type| self.scores.get(&player_type).copied().unwrap_or_default();
+            let score = rules::Score {
+                black: score(PlayerType::Black),
+                white: score(PlayerType::White),
+            };
+            match self.rule_set.winner(&score) {
+                Player::Black => Some(PlayerType::Black),
+                Player::White => Some(PlayerType::White),
+                Player::None => None,
+            }
+        }
+
+        // The rule set that was played and how the game ended.
+        pub fn summary(&self) -> String {
+            match self.winner() {
+                Some(winner) => format!("{}: {} wins", self.rule_set, winner),
+                None => format!("{}: Draw", self.rule_set),
+            }
+        }
     }
 
     #[derive(Debug, Clone)]
@@ -163,20 +192,52 @@ mod system {
             entities.push(camera);
 
             // spawn ui here
+            let font = asset_server.load::<Font, _>("fonts/NotoSans-Regular.ttf");
+            let root = commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .id();
+            entities.push(root);
+
+            // the header says which rule set decided the winner
+            let header = commands
+                .spawn(
+                    TextBundle::from_section(
+                        event.summary(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: data::HEADER_FONT_SIZE,
+                            color: settings.text_color,
+                        },
+                    )
+                    .with_style(Style {
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    }),
+                )
+                .id();
+
             let mut parent = commands.spawn(NodeBundle {
                 style: Style {
                     display: Display::Flex,
                     flex_direction: FlexDirection::Row,
                     width: Val::Percent(100.),
-                    height: Val::Percent(100.),
+                    flex_grow: 1.,
                     ..default()
                 },
                 ..default()
             });
-            entities.push(parent.id());
+            let parent_id = parent.id();
 
             parent.with_children(|builder| {
-                let font = asset_server.load::<Font, _>("fonts/NotoSans-Regular.ttf");
                 let button_data = [
                     (data::PlayerType::Black, data::ButtonType::Proceed),
                     (data::PlayerType::White, data::ButtonType::Proceed),
@@ -213,6 +274,7 @@ mod system {
                     entities.push(new_button);
                 }
             });
+            commands.entity(root).push_children(&[header, parent_id]);
 
             Ok(())
         } else {
@@ -257,6 +319,7 @@ mod system {
                 scores: [(data::PlayerType::Black, 1), (data::PlayerType::White, 2)]
                     .into_iter()
                     .collect(),
+                rule_set: Default::default(),
             };
             writer.send(event::ResultEvent(data));
         }
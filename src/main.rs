@@ -1,6 +1,8 @@
 #[allow(dead_code)]
 mod board;
+mod engine;
 mod game_state;
+mod tui;
 
 #[cfg(test)]
 mod bevy_test;
@@ -15,15 +17,89 @@ const GAME_TITLE: &str = "Reversi";
 const WINDOW_RESOLUTION_X: f32 = 1280.;
 const WINDOW_RESOLUTION_Y: f32 = 720.;
 const GAME_SETTINGS_PATH: &str = "game_settings.json";
+const SOLVE_ARG: &str = "--solve";
+const LOAD_ARG: &str = "--load";
+const TUI_ARG: &str = "--tui";
+const ENGINE_ARG: &str = "--engine";
+const HOST_ARG: &str = "--host";
+const JOIN_ARG: &str = "--join";
+const SPECTATE_ARG: &str = "--spectate";
+const REPLAY_ARG: &str = "--replay";
+const USAGE: &str = "usage: reversi [--tui | --engine | --solve <positions> | --load <save> \
+     | --replay <transcript> | --host <port> | --join <address> | --spectate <address>]";
 
 fn main() {
-    setup_game();
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some(SOLVE_ARG) => {
+            let path = argument(args.get(2), SOLVE_ARG, "a position file");
+            or_exit(solve_positions(path), &format!("Cannot solve {}", path));
+        }
+        Some(TUI_ARG) => or_exit(run_tui(), "Cannot play in the terminal"),
+        Some(ENGINE_ARG) => or_exit(run_engine(), "Cannot run the engine"),
+        Some(LOAD_ARG) => {
+            let path = argument(args.get(2), LOAD_ARG, "a save file");
+            setup_game(GameSource::SavedGame(path), None);
+        }
+        Some(HOST_ARG) => {
+            let port = argument(
+                args.get(2).and_then(|port| port.parse().ok()),
+                HOST_ARG,
+                "a port",
+            );
+            setup_game(GameSource::NewGame, Some(NetworkMode::Host(port)));
+        }
+        Some(JOIN_ARG) => {
+            let address = argument(args.get(2), JOIN_ARG, "a host address");
+            setup_game(GameSource::NewGame, Some(NetworkMode::Join(address)));
+        }
+        Some(SPECTATE_ARG) => {
+            let address = argument(args.get(2), SPECTATE_ARG, "a game address");
+            setup_game(GameSource::NewGame, Some(NetworkMode::Spectate(address)));
+        }
+        Some(REPLAY_ARG) => {
+            let path = argument(args.get(2), REPLAY_ARG, "a transcript file");
+            setup_game(GameSource::Replay(path), None);
+        }
+        _ => setup_game(GameSource::NewGame, None),
+    }
+}
+
+// The value after an option, a missing one is reported with the usage.
+fn argument<T>(value: Option<T>, option: &str, expected: &str) -> T {
+    or_exit(
+        value.ok_or_else(|| format!("{} needs {}\n{}", option, expected, USAGE)),
+        "Invalid arguments",
+    )
 }
 
 #[derive(Debug)]
 enum LoadPluginError {
     IO(std::io::Error),
     Read(serde_json::Error),
+    Save(game_state::save::SaveError),
+    Network(game_state::network::NetworkError),
+    Layout(game_state::layout::LayoutError),
+}
+
+impl std::fmt::Display for LoadPluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadPluginError::IO(e) => write!(f, "{}", e),
+            LoadPluginError::Read(e) => write!(f, "cannot read settings: {}", e),
+            LoadPluginError::Save(e) => write!(f, "{}", e),
+            LoadPluginError::Network(e) => write!(f, "{}", e),
+            LoadPluginError::Layout(e) => write!(f, "cannot set up the board: {}", e),
+        }
+    }
+}
+
+// The game can't start, tell why rather than panicking.
+fn or_exit<T, E: std::fmt::Display>(result: Result<T, E>, context: &str) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}: {}", context, e);
+        std::process::exit(1)
+    })
 }
 
 impl From<std::io::Error> for LoadPluginError {
@@ -38,23 +114,190 @@ impl From<serde_json::Error> for LoadPluginError {
     }
 }
 
+impl From<game_state::save::SaveError> for LoadPluginError {
+    fn from(value: game_state::save::SaveError) -> Self {
+        LoadPluginError::Save(value)
+    }
+}
+
+impl From<game_state::network::NetworkError> for LoadPluginError {
+    fn from(value: game_state::network::NetworkError) -> Self {
+        LoadPluginError::Network(value)
+    }
+}
+
+impl From<game_state::layout::LayoutError> for LoadPluginError {
+    fn from(value: game_state::layout::LayoutError) -> Self {
+        LoadPluginError::Layout(value)
+    }
+}
+
 fn load_game_state_plugin(path: &str) -> Result<GameStatePlugin, LoadPluginError> {
     let file = std::fs::File::open(path)?;
     let reader = std::io::BufReader::new(file);
-    let game_state_plugin = serde_json::from_reader(reader)?;
+    let game_state_plugin: GameStatePlugin = serde_json::from_reader(reader)?;
+    // every setting of the board is used to set up a game
+    game_state_plugin.game_plugin.new_game()?;
     Ok(game_state_plugin)
 }
 
-fn setup_game() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                resolution: [WINDOW_RESOLUTION_X, WINDOW_RESOLUTION_Y].into(),
-                title: GAME_TITLE.to_string(),
-                ..default()
-            }),
+// Solve every position in the file exactly and print the results, no window is opened.
+fn solve_positions(path: &str) -> Result<(), std::io::Error> {
+    use game_state::{
+        ai::endgame,
+        rules::{RuleSet, SearchBoard},
+    };
+
+    let text = std::fs::read_to_string(path)?;
+    for (index, position) in endgame::parse_positions(&text).into_iter().enumerate() {
+        match position {
+            Ok((board, player)) => {
+                let result = endgame::solve(&SearchBoard::from(&board), player, RuleSet::Standard);
+                println!(
+                    "position {}: best move {:?}, disc difference {}, nodes {}",
+                    index, result.best_move, result.disc_difference, result.nodes
+                );
+            }
+            Err(e) => println!("position {}: {:?}", index, e),
+        }
+    }
+    Ok(())
+}
+
+// Play in the terminal with the board and players from the settings, no window is opened.
+fn run_tui() -> Result<(), LoadPluginError> {
+    let game_state_plugin = load_game_state_plugin(GAME_SETTINGS_PATH)?;
+    let game_plugin = &game_state_plugin.game_plugin;
+    let game_data = game_plugin.new_game()?;
+    let mut tui = tui::Tui::new(game_data, game_plugin.player_controllers().clone());
+    tui.run(std::io::stdin().lock(), &mut std::io::stdout())?;
+    Ok(())
+}
+
+// Answer engine protocol requests on stdin with the first computer player from the settings.
+fn run_engine() -> Result<(), LoadPluginError> {
+    use game_state::{ai, data::Turn};
+
+    let game_state_plugin = load_game_state_plugin(GAME_SETTINGS_PATH)?;
+    let player_controllers = game_state_plugin.game_plugin.player_controllers();
+    let player_controller = [Turn::White, Turn::Black]
+        .iter()
+        .filter_map(|turn| player_controllers.get(turn))
+        .find(|player_controller| !player_controller.is_human())
+        .cloned()
+        .unwrap_or_else(|| ai::PlayerController::AlphaBeta(Default::default()));
+    let mut engine = engine::Engine::new(player_controller)
+        .with_rule_set(game_state_plugin.game_plugin.rule_set());
+    engine.run(std::io::stdin().lock(), &mut std::io::stdout())?;
+    Ok(())
+}
+
+// The game state starts in the game, so a saved game resumes right away.
+fn load_saved_game(
+    game_state_plugin: GameStatePlugin,
+    path: &str,
+) -> Result<GameStatePlugin, LoadPluginError> {
+    let game_data = game_state::save::load(path)?;
+    Ok(GameStatePlugin {
+        game_plugin: game_state_plugin.game_plugin.with_saved_game(game_data)?,
+        ..game_state_plugin
+    })
+}
+
+// A transcript is checked move by move before the window opens.
+fn load_replay(
+    game_state_plugin: GameStatePlugin,
+    path: &str,
+) -> Result<GameStatePlugin, game_state::replay::ReplayError> {
+    let size = game_state_plugin.game_plugin.board_settings().size();
+    let transcript = game_state::replay::load(path, size)?;
+    Ok(GameStatePlugin {
+        game_plugin: game_state_plugin.game_plugin.with_replay(transcript)?,
+        ..game_state_plugin
+    })
+}
+
+enum GameSource<'a> {
+    NewGame,
+    SavedGame(&'a str), // path of a saved game
+    Replay(&'a str),    // path of a transcript to play back
+}
+
+enum NetworkMode<'a> {
+    Host(u16),         // port to listen on
+    Join(&'a str),     // address of the host
+    Spectate(&'a str), // address of a game broadcasting to spectators
+}
+
+// Waits for the other player before the window opens, both sides need the same settings.
+fn connect(
+    game_state_plugin: &GameStatePlugin,
+    network_mode: NetworkMode,
+) -> Result<game_state::plugin::Connection, LoadPluginError> {
+    use game_state::{
+        network::{Peer, Spectator},
+        plugin::Connection,
+    };
+
+    let game_plugin = &game_state_plugin.game_plugin;
+    let board_settings = game_plugin.board_settings();
+    let first_turn = game_plugin.first_turn();
+    let handicap = game_plugin.handicap().copied();
+    let rule_set = game_plugin.rule_set();
+    Ok(match network_mode {
+        NetworkMode::Host(port) => Connection::Peer(Peer::host(
+            port,
+            board_settings,
+            first_turn,
+            handicap,
+            rule_set,
+        )?),
+        NetworkMode::Join(address) => Connection::Peer(Peer::join(
+            address,
+            board_settings,
+            first_turn,
+            handicap,
+            rule_set,
+        )?),
+        NetworkMode::Spectate(address) => Connection::Spectator(Spectator::connect(address)?),
+    })
+}
+
+fn setup_game(game_source: GameSource, network_mode: Option<NetworkMode>) {
+    let mut game_state_plugin = or_exit(
+        load_game_state_plugin(GAME_SETTINGS_PATH),
+        &format!("Cannot load {}", GAME_SETTINGS_PATH),
+    );
+    match game_source {
+        GameSource::NewGame => (),
+        GameSource::SavedGame(path) => {
+            game_state_plugin = or_exit(
+                load_saved_game(game_state_plugin, path),
+                &format!("Cannot load {}", path),
+            );
+        }
+        GameSource::Replay(path) => {
+            game_state_plugin = or_exit(
+                load_replay(game_state_plugin, path),
+                &format!("Cannot replay {}", path),
+            );
+        }
+    }
+    let connection =
+        network_mode.map(|mode| or_exit(connect(&game_state_plugin, mode), "Cannot connect"));
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            resolution: [WINDOW_RESOLUTION_X, WINDOW_RESOLUTION_Y].into(),
+            title: GAME_TITLE.to_string(),
             ..default()
-        }))
-        .add_plugins(load_game_state_plugin(GAME_SETTINGS_PATH).unwrap())
-        .run();
+        }),
+        ..default()
+    }))
+    .add_plugins(game_state_plugin);
+    if let Some(connection) = connection {
+        app.add_plugins(game_state::plugin::NetworkPlugin::new(connection));
+    }
+    app.run();
 }
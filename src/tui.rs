@@ -0,0 +1,236 @@
+// Text front end for terminals without a window, played on the same GameData as the window.
+use std::io::{BufRead, Write};
+
+use bevy::utils::HashMap;
+
+use crate::game_state::{
+    ai,
+    data::{GameData, HistoryCommand, Player, Turn},
+    notation,
+    rules::RuleSet,
+};
+
+const QUIT_COMMANDS: [&str; 2] = ["quit", "exit"];
+const UNDO_COMMAND: &str = "undo";
+const REDO_COMMAND: &str = "redo";
+
+fn player_name(player: Player) -> &'static str {
+    match player {
+        Player::Black => "Black (X)",
+        Player::White => "White (O)",
+        Player::None => "Nobody",
+    }
+}
+
+fn cell_char(player: Player) -> char {
+    match player {
+        Player::Black => 'X',
+        Player::White => 'O',
+        Player::None => '.',
+    }
+}
+
+// Column letters on top and row numbers on the left, legal moves marked with '*'.
+pub fn render_board(game_data: &GameData) -> String {
+    let board = game_data.board();
+    let legal_moves = game_data.legal_moves();
+    let size_x = board.size.x() as i64;
+    let size_y = board.size.y() as i64;
+
+    let columns: String = (0..size_x)
+        .map(|x| format!(" {}", notation::column_char(x)))
+        .collect();
+    let mut text = format!("   {}\n", columns);
+    for y in 0..size_y {
+        let row: String = (0..size_x)
+            .map(|x| {
+                let pos = (x, y).into();
+                match board.cell_ref(&pos) {
+                    Some(Player::None) if legal_moves.contains(&pos) => " *".to_string(),
+                    Some(player) => format!(" {}", cell_char(*player)),
+                    None => " #".to_string(), // blocked
+                }
+            })
+            .collect();
+        text += &format!("{:>3}{}\n", y + 1, row);
+    }
+
+    let score = game_data.score();
+    text += &format!(
+        "{} {} - {} {}\n",
+        player_name(Player::Black),
+        score.black,
+        score.white,
+        player_name(Player::White)
+    );
+    text
+}
+
+pub struct Tui {
+    game_data: GameData,
+    player_controllers: HashMap<Turn, ai::PlayerController>,
+}
+
+impl Tui {
+    pub fn new(
+        game_data: GameData,
+        player_controllers: HashMap<Turn, ai::PlayerController>,
+    ) -> Self {
+        Tui {
+            game_data,
+            player_controllers,
+        }
+    }
+
+    // Plays until the game is over, the input ends or the player quits.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> std::io::Result<()> {
+        let mut lines = input.lines();
+        loop {
+            if self.game_data.is_game_over() {
+                self.write_board(output)?;
+                return self.write_result(output);
+            }
+
+            let player = self.game_data.current_player();
+            if self.game_data.legal_moves().is_empty() {
+                writeln!(
+                    output,
+                    "{} has no legal move and passes.",
+                    player_name(player)
+                )?;
+                self.game_data.pass().unwrap();
+                continue;
+            }
+
+            self.write_board(output)?;
+            if !ai::is_human(&self.player_controllers, self.game_data.turn()) {
+                let player_controller = &self.player_controllers[self.game_data.turn()];
+                let position = player_controller
+                    .choose_move(&self.game_data)
+                    .expect("a computer player with legal moves chooses one");
+                self.game_data.play(&position).unwrap();
+                writeln!(
+                    output,
+                    "{} plays {}",
+                    player_name(player),
+                    notation::to_string(&position)
+                )?;
+                continue;
+            }
+
+            let legal_moves: Vec<String> = self
+                .game_data
+                .legal_moves()
+                .iter()
+                .map(notation::to_string)
+                .collect();
+            write!(
+                output,
+                "{} to move [{}]: ",
+                player_name(player),
+                legal_moves.join(" ")
+            )?;
+            output.flush()?;
+
+            let Some(line) = lines.next() else {
+                writeln!(output)?;
+                return Ok(());
+            };
+            let line = line?;
+            let command = line.trim().to_ascii_lowercase();
+            match command.as_str() {
+                "" => (),
+                c if QUIT_COMMANDS.contains(&c) => return Ok(()),
+                UNDO_COMMAND => self.apply_history_command(HistoryCommand::Undo, output)?,
+                REDO_COMMAND => self.apply_history_command(HistoryCommand::Redo, output)?,
+                text => match notation::parse(text) {
+                    Ok(position) => {
+                        if let Err(e) = self.game_data.play(&position) {
+                            writeln!(output, "Illegal move {}: {:?}", text, e)?;
+                        }
+                    }
+                    Err(e) => writeln!(output, "Cannot read move {}: {:?}", text, e)?,
+                },
+            }
+        }
+    }
+
+    fn apply_history_command<W: Write>(
+        &mut self,
+        command: HistoryCommand,
+        output: &mut W,
+    ) -> std::io::Result<()> {
+        if !ai::apply_history_command(&mut self.game_data, &self.player_controllers, command) {
+            writeln!(output, "Nothing to {:?}.", command)?;
+        }
+        Ok(())
+    }
+
+    fn write_board<W: Write>(&self, output: &mut W) -> std::io::Result<()> {
+        write!(output, "\n{}", render_board(&self.game_data))
+    }
+
+    fn write_result<W: Write>(&self, output: &mut W) -> std::io::Result<()> {
+        let score = self.game_data.score();
+        writeln!(
+            output,
+            "Game over: Black {} - White {}",
+            score.black, score.white
+        )?;
+        if self.game_data.rule_set() != RuleSet::Standard {
+            writeln!(output, "Rule set: {}", self.game_data.rule_set())?;
+        }
+        match self.game_data.winner() {
+            Player::None => writeln!(output, "Draw.")?,
+            winner => writeln!(output, "{} wins.", player_name(winner))?,
+        }
+        if let Some(handicap) = self.game_data.handicap() {
+            writeln!(output, "Handicap: {}", handicap)?;
+        }
+        writeln!(output, "Transcript:\n{}", self.game_data.transcript())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn new_game() -> GameData {
+        GameData::new(Turn::Black, 8.try_into().unwrap(), 8.try_into().unwrap())
+    }
+
+    fn run(tui: &mut Tui, input: &str) -> String {
+        let mut output = vec![];
+        tui.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn plays_moves_from_input() {
+        let mut tui = Tui::new(new_game(), HashMap::default());
+        let output = run(&mut tui, "c5\nz9\nc5\ne6\nundo\nc6\nquit\n");
+        assert!(output.contains("Black (X) to move [e3 f4 c5 d6]"));
+        assert!(output.contains("Illegal move z9"));
+        assert!(output.contains("Illegal move c5: Occupied"));
+        assert_eq!(tui.game_data.transcript(), "f5f6");
+    }
+
+    #[test]
+    fn computer_finishes_the_game() {
+        let player_controllers = [Turn::Black, Turn::White]
+            .into_iter()
+            .map(|turn| {
+                let settings = ai::mcts::MctsSettings {
+                    iterations: 1,
+                    seed: Some(0),
+                    ..Default::default()
+                };
+                (turn, ai::PlayerController::Mcts(settings))
+            })
+            .collect();
+        let mut tui = Tui::new(new_game(), player_controllers);
+        let output = run(&mut tui, "");
+        assert!(output.contains("Game over"));
+        assert!(output.contains(&tui.game_data.transcript()));
+    }
+}